@@ -1,33 +1,317 @@
-use crate::models::HoudiniVersion;
-use crate::utils::config::{get_houdini_exe_txt_path, get_houdini_root_txt_path};
+use crate::models::{
+    HoudiniBuildType, HoudiniExecutable, HoudiniRoot, HoudiniRootStatus, HoudiniRootsFile, HoudiniVersion, HOUDINI_EXECUTABLES,
+};
+use crate::utils::config::{
+    ensure_config_dir, get_houdini_exe_txt_path, get_houdini_root_txt_path, get_houdini_roots_json_path,
+    load_json_file_checked, normalize_path, save_json_file,
+};
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use tauri::State;
 use crate::AppState;
 
 #[tauri::command]
 pub fn list_houdini_versions(state: State<'_, AppState>) -> Result<Vec<HoudiniVersion>, String> {
-    let roots = common_houdini_roots();
+    Ok(installed_versions(&state))
+}
 
-    for root in &roots {
-        let versions = list_houdini_versions_from_root(root);
-        if !versions.is_empty() {
-            return Ok(versions);
-        }
+pub fn installed_versions(state: &AppState) -> Vec<HoudiniVersion> {
+    let mut versions: Vec<HoudiniVersion> = Vec::new();
+    for (root, _) in houdini_install_roots(state) {
+        versions.extend(list_houdini_versions_from_root(&root));
     }
 
-    let versions = list_houdini_versions_from_root(&state.config_paths.root);
-    if !versions.is_empty() {
-        return Ok(versions);
+    if let Some(hfs) = hfs_houdini_version() {
+        versions.push(hfs);
     }
 
-    Ok(Vec::new())
+    merge_versions(versions)
+}
+
+/// Every directory scanned for installs, paired with whether the user added it:
+/// the platform defaults, the saved `houdini_root.txt` override, the roots from
+/// `houdini_roots.json` and the config root.
+fn houdini_install_roots(state: &AppState) -> Vec<(PathBuf, bool)> {
+    let mut roots: Vec<(PathBuf, bool)> = common_houdini_roots()
+        .into_iter()
+        .map(|r| (r, false))
+        .collect();
+    if let Ok(Some(saved)) = load_saved_houdini_root() {
+        roots.push((saved, true));
+    }
+    roots.extend(load_user_houdini_roots().into_iter().map(|r| (r, true)));
+    roots.push((state.config_paths.root.clone(), false));
+
+    let mut seen: HashSet<PathBuf> = HashSet::new();
+    roots.retain(|(r, _)| seen.insert(canonical_key(r)));
+    roots
+}
+
+fn load_user_houdini_roots() -> Vec<PathBuf> {
+    load_user_houdini_roots_checked().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        Vec::new()
+    })
+}
+
+/// The saved roots; fails if the file exists but is invalid, so an add or
+/// remove never writes over it.
+fn load_user_houdini_roots_checked() -> Result<Vec<PathBuf>, String> {
+    Ok(load_json_file_checked::<HoudiniRootsFile>(&get_houdini_roots_json_path())?
+        .map(|f| f.roots)
+        .unwrap_or_default())
+}
+
+fn save_user_houdini_roots(roots: Vec<PathBuf>) -> Result<(), String> {
+    ensure_config_dir();
+    save_json_file(&get_houdini_roots_json_path(), &HoudiniRootsFile { roots })
+        .map_err(|e| format!("Failed to save Houdini roots: {}", e))
+}
+
+#[tauri::command]
+pub fn list_houdini_roots(state: State<'_, AppState>) -> Result<Vec<HoudiniRoot>, String> {
+    let roots = houdini_install_roots(&state)
+        .into_iter()
+        .map(|(path, user_defined)| {
+            let version_count = list_houdini_versions_from_root(&path).len();
+            let status = if !path.is_dir() {
+                HoudiniRootStatus::Missing
+            } else if version_count == 0 {
+                HoudiniRootStatus::Empty
+            } else {
+                HoudiniRootStatus::Reachable
+            };
+            HoudiniRoot {
+                path,
+                status,
+                version_count,
+                user_defined,
+            }
+        })
+        .collect();
+    Ok(roots)
+}
+
+#[tauri::command]
+pub fn add_houdini_root(root: PathBuf, state: State<'_, AppState>) -> Result<Vec<HoudiniRoot>, String> {
+    let root = normalize_path(root);
+    let key = canonical_key(&root);
+
+    let mut roots = load_user_houdini_roots_checked()?;
+    if !roots.iter().any(|r| canonical_key(r) == key) {
+        roots.push(root);
+        save_user_houdini_roots(roots)?;
+    }
+    list_houdini_roots(state)
+}
+
+#[tauri::command]
+pub fn remove_houdini_root(root: PathBuf, state: State<'_, AppState>) -> Result<Vec<HoudiniRoot>, String> {
+    let key = canonical_key(&normalize_path(root));
+
+    let mut roots = load_user_houdini_roots_checked()?;
+    let before = roots.len();
+    roots.retain(|r| canonical_key(r) != key);
+    if roots.len() != before {
+        save_user_houdini_roots(roots)?;
+    }
+    list_houdini_roots(state)
 }
 
 fn common_houdini_roots() -> Vec<PathBuf> {
-    vec![
-        PathBuf::from(r"C:\Program Files\Side Effects Software"),
-        PathBuf::from(r"C:\Program Files\SideFX"),
-    ]
+    #[cfg(target_os = "windows")]
+    {
+        vec![
+            PathBuf::from(r"C:\Program Files\Side Effects Software"),
+            PathBuf::from(r"C:\Program Files\SideFX"),
+        ]
+    }
+    #[cfg(target_os = "macos")]
+    {
+        vec![
+            PathBuf::from("/Applications/Houdini"),
+        ]
+    }
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        vec![
+            PathBuf::from("/opt"),
+            PathBuf::from("/usr/local"),
+        ]
+    }
+}
+
+fn is_houdini_dir_name(name: &str) -> bool {
+    name.starts_with("Houdini") || name.starts_with("hfs")
+}
+
+fn find_bin_dir(install_dir: &Path) -> Option<PathBuf> {
+    let candidates = [
+        install_dir.join("bin"),
+        install_dir.join("Frameworks").join("Houdini.framework")
+            .join("Versions").join("Current").join("Resources").join("bin"),
+    ];
+    candidates.into_iter().find(|p| p.is_dir())
+}
+
+fn canonical_key(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Reads the full build number from the toolkit headers shipped with every install.
+fn read_installed_version(bin_path: &Path) -> Option<(u32, u32, u32)> {
+    let resources = bin_path.parent()?;
+    let header = resources.join("toolkit").join("include").join("SYS").join("SYS_Version.h");
+    let content = std::fs::read_to_string(header).ok()?;
+    // Match the macro name as a whole token; SYS_VERSION_FULL_INT is defined
+    // in the same header.
+    content.lines()
+        .find_map(|line| {
+            let mut tokens = line.split_whitespace();
+            match (tokens.next(), tokens.next(), tokens.next()) {
+                (Some("#define"), Some("SYS_VERSION_FULL"), Some(value)) => Some(value),
+                _ => None,
+            }
+        })
+        .and_then(|value| HoudiniVersion::parse_version(value.trim_matches('"')))
+}
+
+fn houdini_version_from_dir(path: &Path, root: &Path) -> Option<HoudiniVersion> {
+    let bin_path = find_bin_dir(path)?;
+    let name = path.file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+
+    // The directory name may be a short alias like "hfs20.5", so prefer the
+    // version the install reports about itself.
+    let (major, minor, build) = read_installed_version(&bin_path)
+        .or_else(|| HoudiniVersion::parse_version(&name))
+        .unwrap_or((0, 0, 0));
+
+    let executables = detect_executables(&bin_path);
+    let python_version = detect_python_version(&bin_path);
+    let build_type = detect_build_type(path);
+
+    Some(HoudiniVersion {
+        name,
+        path: path.to_path_buf(),
+        bin_path,
+        root: root.to_path_buf(),
+        major,
+        minor,
+        build,
+        executables,
+        python_version,
+        build_type,
+        is_alias: path.is_symlink(),
+    })
+}
+
+/// Parses the Python version out of the folder names an install uses for its
+/// bundled interpreter: "python3.11libs", "python311" (Windows) or "python3.11".
+fn parse_python_dir_name(name: &str) -> Option<(u32, u32)> {
+    let rest = name.strip_prefix("python")?;
+    let rest = rest.strip_suffix("libs").unwrap_or(rest);
+    if let Some((major, minor)) = rest.split_once('.') {
+        return Some((major.parse().ok()?, minor.parse().ok()?));
+    }
+    if rest.len() >= 2 && rest.chars().all(|c| c.is_ascii_digit()) {
+        let (major, minor) = rest.split_at(1);
+        return Some((major.parse().ok()?, minor.parse().ok()?));
+    }
+    None
+}
+
+fn detect_python_version(bin_path: &Path) -> Option<String> {
+    let resources = bin_path.parent()?;
+    let search_dirs = [
+        resources.join("houdini"),
+        resources.to_path_buf(),
+        resources.join("python").join("lib"),
+    ];
+
+    search_dirs.iter()
+        .filter_map(|dir| std::fs::read_dir(dir).ok())
+        .flat_map(|entries| entries.flatten())
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| parse_python_dir_name(&entry.file_name().to_string_lossy()))
+        .max()
+        .map(|(major, minor)| format!("{}.{}", major, minor))
+}
+
+/// Installs don't record whether they came from a production or a daily
+/// installer, so this relies on the directory naming studios use for dailies.
+fn detect_build_type(path: &Path) -> HoudiniBuildType {
+    let name = path.file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_lowercase();
+    if name.contains("daily") || name.contains("dev") {
+        HoudiniBuildType::Daily
+    } else if name.contains("prod") {
+        HoudiniBuildType::Production
+    } else {
+        HoudiniBuildType::Unknown
+    }
+}
+
+fn detect_executables(bin_path: &Path) -> Vec<HoudiniExecutable> {
+    HOUDINI_EXECUTABLES.iter()
+        .filter_map(|name| {
+            [format!("{}.exe", name), name.to_string()]
+                .into_iter()
+                .map(|file| bin_path.join(file))
+                .find(|p| p.is_file())
+                .map(|path| HoudiniExecutable {
+                    name: name.to_string(),
+                    path,
+                })
+        })
+        .collect()
+}
+
+fn sort_versions(versions: &mut [HoudiniVersion]) {
+    versions.sort_by(|a, b| {
+        b.version_tuple().cmp(&a.version_tuple())
+            .then_with(|| b.name.cmp(&a.name))
+    });
+}
+
+/// Resolves a preset's Houdini reference against the installed versions. The
+/// reference can be an exact name or path, or a spec like "20.5" or "latest 20.x",
+/// in which case the newest matching build wins.
+pub fn resolve_version<'a>(spec: &str, versions: &'a [HoudiniVersion]) -> Option<&'a HoudiniVersion> {
+    let spec = spec.trim();
+    if spec.is_empty() {
+        return None;
+    }
+
+    if let Some(v) = versions.iter().find(|v| v.name == spec || v.path == Path::new(spec)) {
+        return Some(v);
+    }
+
+    versions.iter()
+        .filter(|v| v.matches_spec(spec))
+        .max_by(|a, b| a.version_tuple().cmp(&b.version_tuple()))
+}
+
+#[tauri::command]
+pub fn resolve_houdini_version(spec: String, state: State<'_, AppState>) -> Result<HoudiniVersion, String> {
+    let versions = installed_versions(&state);
+    resolve_version(&spec, &versions)
+        .cloned()
+        .ok_or_else(|| format!("No installed Houdini version matches '{}'", spec))
+}
+
+fn hfs_houdini_version() -> Option<HoudiniVersion> {
+    let hfs = std::env::var("HFS").ok()?;
+    let hfs = hfs.trim();
+    if hfs.is_empty() {
+        return None;
+    }
+    let hfs = Path::new(hfs);
+    houdini_version_from_dir(hfs, hfs.parent().unwrap_or(hfs))
 }
 
 pub fn list_houdini_versions_from_root(install_root: &Path) -> Vec<HoudiniVersion> {
@@ -40,49 +324,75 @@ pub fn list_houdini_versions_from_root(install_root: &Path) -> Vec<HoudiniVersio
     if let Ok(entries) = std::fs::read_dir(install_root) {
         for entry in entries.flatten() {
             let path = entry.path();
-            if path.is_dir() {
-                let name = path.file_name()
-                    .unwrap_or_default()
-                    .to_string_lossy()
-                    .to_string();
-
-                if name.starts_with("Houdini") {
-                    let bin_path = path.join("bin");
-                    if bin_path.is_dir() {
-                        versions.push(HoudiniVersion {
-                            name: name.clone(),
-                            path: path.clone(),
-                            bin_path,
-                        });
-                    }
+            if !path.is_dir() {
+                continue;
+            }
+
+            let name = path.file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+            if !is_houdini_dir_name(&name) {
+                continue;
+            }
+
+            if let Some(version) = houdini_version_from_dir(&path, install_root) {
+                versions.push(version);
+            }
+        }
+    }
+
+    merge_versions(versions)
+}
+
+/// Drops installs that resolve to the same directory and sorts newest first.
+/// `/opt/hfs20.5` is usually a symlink to `/opt/hfs20.5.332`, so the real
+/// directory is kept over the alias.
+fn merge_versions(versions: Vec<HoudiniVersion>) -> Vec<HoudiniVersion> {
+    let mut merged: Vec<HoudiniVersion> = Vec::new();
+    let mut seen: HashMap<PathBuf, usize> = HashMap::new();
+
+    for version in versions {
+        let key = canonical_key(&version.path);
+        match seen.get(&key) {
+            Some(&idx) => {
+                if !version.path.is_symlink() && merged[idx].path.is_symlink() {
+                    merged[idx] = version;
                 }
             }
+            None => {
+                seen.insert(key, merged.len());
+                merged.push(version);
+            }
         }
     }
 
-    versions.sort_by(|a, b| b.name.cmp(&a.name));
-    versions
+    sort_versions(&mut merged);
+    merged
 }
 
+/// Product tiers tried, in order, when no executable is requested explicitly.
+const DEFAULT_EXECUTABLES: &[&str] = &["houdinifx", "houdini", "houdinicore", "happrentice"];
+
 #[tauri::command]
 pub fn get_houdini_exe_path(
     version_path: String,
     exe_name: Option<String>,
 ) -> Result<String, String> {
-    let version_path = PathBuf::from(version_path);
-    let exe_name = exe_name.unwrap_or_else(|| "houdinifx.exe".to_string());
-
-    let exe_path = version_path.join("bin").join(&exe_name);
-    let fallback_path = version_path.join("bin").join("houdini.exe");
+    let path = PathBuf::from(&version_path);
+    let root = path.parent().unwrap_or(&path).to_path_buf();
+    let version = houdini_version_from_dir(&path, &root)
+        .ok_or_else(|| format!("Not a Houdini install: {}", version_path))?;
 
-    if exe_path.exists() {
-        return Ok(exe_path.to_string_lossy().to_string());
-    }
-    if fallback_path.exists() {
-        return Ok(fallback_path.to_string_lossy().to_string());
-    }
+    let exe = match exe_name.as_deref().map(str::trim).filter(|n| !n.is_empty()) {
+        Some(name) => version.find_executable(name)
+            .ok_or_else(|| format!("Executable '{}' not found in {}", name, version.bin_path.display()))?,
+        None => DEFAULT_EXECUTABLES.iter()
+            .find_map(|name| version.find_executable(name))
+            .ok_or_else(|| format!("No Houdini executable found in {}", version.bin_path.display()))?,
+    };
 
-    Ok(exe_path.to_string_lossy().to_string())
+    Ok(exe.path.to_string_lossy().to_string())
 }
 
 #[tauri::command]
@@ -1,47 +1,266 @@
+use crate::commands::houdini::{installed_versions, resolve_version};
+use crate::commands::packages::scan_packages;
+use crate::commands::presets::preset_priority;
+use crate::models::{EnvMethod, EnvSource, EnvVariable, GlobalVarsFile, LaunchRequest, Package};
+use crate::utils::config::{get_staging_root, load_json_file};
+use crate::utils::conflicts::prioritized_load_order;
+use crate::utils::dependencies::resolve_dependencies;
+use crate::utils::expand::Expander;
+use crate::utils::expression::ExprContext;
+use crate::utils::script_export::{render_launch_script, ScriptFormat};
+use crate::utils::staging::{remove_staged, stage_packages, stage_packages_into};
+use std::collections::{BTreeMap, BTreeSet};
 use std::env;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use tauri::State;
 use crate::AppState;
 
+/// Variables the launcher sets on top of the process environment, in the
+/// order they are applied, each with the source it came from.
+fn launcher_env(package_dir: &str, request: &LaunchRequest, state: &AppState) -> Vec<(String, String, String)> {
+    let mut env = vec![
+        ("HOUDINI_PACKAGE_DIR".to_string(), package_dir.to_string(), "launcher".to_string()),
+        ("CONFIG_ROOT_PATH".to_string(), request.config_root.clone(), "launcher".to_string()),
+    ];
+
+    if let Some(globals) = load_json_file::<GlobalVarsFile>(&state.config_paths.global_vars_file) {
+        let mut vars: Vec<(String, String)> = globals.vars.into_iter().collect();
+        vars.sort();
+        for (k, v) in vars {
+            env.push((k, v, "global_vars".to_string()));
+        }
+    }
+
+    for (k, v) in &request.env_vars {
+        env.push((k.clone(), v.clone(), "preset".to_string()));
+    }
+    env
+}
+
+/// The packages a launch loads, in the order Houdini reads them from the
+/// staged directory: the given names, or the user's enabled packages, plus
+/// everything they require, ordered by the launched preset's `priority` list.
+fn launch_packages<'a>(
+    available: &'a [Package],
+    packages: Option<Vec<String>>,
+    priority: &[String],
+    ctx: Option<&ExprContext>,
+) -> Vec<&'a Package> {
+    let selected = packages.unwrap_or_else(|| {
+        available.iter()
+            .filter(|p| p.enabled)
+            .map(|p| p.name.clone())
+            .collect()
+    });
+
+    let plan = resolve_dependencies(&selected, available, ctx);
+    let chosen: Vec<&Package> = plan.packages.iter()
+        .filter_map(|name| available.iter().find(|p| &p.name == name))
+        .collect();
+    prioritized_load_order(&chosen, priority)
+}
+
+/// Stands in for the directory a launch stages its packages into, which is
+/// only created when Houdini starts.
+fn staged_dir_placeholder() -> String {
+    get_staging_root().join("<launch>").to_string_lossy().to_string()
+}
+
+/// Where a saved script's packages are staged: next to the script.
+fn export_package_dir(script: &Path) -> PathBuf {
+    let stem = script.file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "launch".to_string());
+    script.with_file_name(format!("{}_packages", stem))
+}
+
 #[tauri::command]
-pub async fn launch_houdini(
-    exe_path: String,
-    package_dir: String,
-    config_root: String,
-    env_vars: Vec<(String, String)>,
-    state: State<'_, AppState>,
-) -> Result<(), String> {
-    let exe = PathBuf::from(&exe_path);
+pub async fn launch_houdini(request: LaunchRequest, state: State<'_, AppState>) -> Result<(), String> {
+    let exe = PathBuf::from(&request.exe_path);
 
     if !exe.exists() {
-        return Err(format!("Houdini executable not found: {}", exe_path));
+        return Err(format!("Houdini executable not found: {}", request.exe_path));
     }
 
+    let priority = preset_priority(&state.config_paths.presets_file, request.preset.as_deref())?;
+    let available = scan_packages(&state.config_paths);
+    let chosen = launch_packages(&available, request.packages.clone(), &priority, None);
+    let staged_dir = stage_packages(&chosen, &priority)?;
+    let staged_dir_str = staged_dir.to_string_lossy().to_string();
+
     let mut env = env::vars().collect::<Vec<_>>();
-    env.push(("HOUDINI_PACKAGE_DIR".to_string(), package_dir));
-    env.push(("CONFIG_ROOT_PATH".to_string(), config_root));
+    for (key, value, _) in launcher_env(&staged_dir_str, &request, &state) {
+        env.push((key, value));
+    }
 
-    for (k, v) in &env_vars {
-        env.push((k.clone(), v.clone()));
+    let mut command = Command::new(&exe);
+    command.env_clear();
+    for (key, value) in &env {
+        command.env(key, value);
     }
+    command.current_dir(&state.config_paths.root);
 
     #[cfg(target_os = "windows")]
     {
         use std::os::windows::process::CommandExt;
         const CREATE_NO_WINDOW: u32 = 0x08000000;
-        let mut command = Command::new(&exe);
-        command.env_clear();
-        for (key, value) in &env {
-            command.env(key, value);
-        }
         command.creation_flags(CREATE_NO_WINDOW);
-        command.current_dir(&state.config_paths.root);
 
-        if let Ok(mut child) = command.spawn() {
+        let mut child = match command.spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                remove_staged(&staged_dir);
+                return Err(format!("Failed to launch Houdini: {}", e));
+            }
+        };
+        child.wait().ok();
+        remove_staged(&staged_dir);
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        use std::process::Stdio;
+        // Own process group so Houdini outlives the launcher and its terminal.
+        command.process_group(0);
+        command.stdin(Stdio::null());
+
+        let mut child = match command.spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                remove_staged(&staged_dir);
+                return Err(format!("Failed to launch Houdini: {}", e));
+            }
+        };
+        // Reap the child in the background so it doesn't linger as a zombie,
+        // and drop its staged packages once it exits.
+        std::thread::spawn(move || {
             child.wait().ok();
-        }
+            remove_staged(&staged_dir);
+        });
     }
 
     Ok(())
 }
+
+/// Computes the environment Houdini will see for a launch without spawning
+/// anything: the process environment, the variables `launch_houdini` sets, and
+/// the contributions of every package that will load, in Houdini's load order.
+#[tauri::command]
+pub fn preview_launch_environment(
+    houdini: Option<String>,
+    request: LaunchRequest,
+    state: State<'_, AppState>,
+) -> Result<Vec<EnvVariable>, String> {
+    let versions = installed_versions(&state);
+    let version = houdini.as_deref().and_then(|h| resolve_version(h, &versions));
+    let ctx = version.map(ExprContext::for_version);
+
+    let staged_dir = staged_dir_placeholder();
+    let mut expander = Expander::for_houdini(version, Path::new(&request.config_root));
+    // The launcher hands these to the process as they are.
+    for (key, value, source) in launcher_env(&staged_dir, &request, &state) {
+        expander.define_literal(&key, &value, &source);
+    }
+
+    let available = scan_packages(&state.config_paths);
+    let priority = preset_priority(&state.config_paths.presets_file, request.preset.as_deref())?;
+    for pkg in launch_packages(&available, request.packages.clone(), &priority, ctx.as_ref()) {
+        // Houdini still evaluates `enable` expressions in staged files.
+        if pkg.spec_allows(ctx.as_ref()) {
+            expander.define_package(pkg, ctx.as_ref());
+        }
+    }
+
+    let process: BTreeMap<String, String> = env::vars().collect();
+    let mut names: BTreeSet<String> = process.keys().cloned().collect();
+    names.extend(expander.defs().iter().map(|d| d.key.clone()));
+
+    let mut variables = Vec::new();
+    for name in names {
+        let mut sources = Vec::new();
+        if let Some(value) = process.get(&name) {
+            sources.push(EnvSource {
+                source: "process".to_string(),
+                value: value.clone(),
+                method: EnvMethod::Replace,
+            });
+        }
+        for def in expander.defs().iter().filter(|d| d.key == name) {
+            sources.push(EnvSource {
+                source: def.source.clone(),
+                value: def.value.clone(),
+                method: def.method,
+            });
+        }
+
+        let (value, error) = match expander.value(&name) {
+            Ok(value) => (value.unwrap_or_default(), None),
+            Err(e) => (String::new(), Some(e)),
+        };
+        variables.push(EnvVariable {
+            name,
+            value,
+            sources,
+            error,
+        });
+    }
+
+    Ok(variables)
+}
+
+/// Writes what `launch_houdini` would do as a shell script, batch file or
+/// dotenv file, so the same launch can be reproduced without the launcher.
+/// Returns the script text; when `output_path` is given it is also saved there
+/// and the chosen packages are staged next to it, in a directory the script
+/// points `HOUDINI_PACKAGE_DIR` at, since the launcher's own staging is
+/// removed when Houdini exits. Without `output_path` nothing is staged and the
+/// text shows the same placeholder directory as the preview.
+#[tauri::command]
+pub fn export_launch_script(
+    format: ScriptFormat,
+    request: LaunchRequest,
+    output_path: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let output_path = output_path.map(|p| {
+        let mut path = PathBuf::from(p);
+        if path.extension().is_none() {
+            path.set_extension(format.extension());
+        }
+        path
+    });
+
+    let priority = preset_priority(&state.config_paths.presets_file, request.preset.as_deref())?;
+    let available = scan_packages(&state.config_paths);
+    let chosen = launch_packages(&available, request.packages.clone(), &priority, None);
+    let package_dir = match &output_path {
+        Some(path) => {
+            let dir = export_package_dir(path);
+            stage_packages_into(&dir, &chosen, &priority)?;
+            dir.to_string_lossy().to_string()
+        }
+        None => staged_dir_placeholder(),
+    };
+
+    let env: Vec<(String, String)> = launcher_env(&package_dir, &request, &state)
+        .into_iter()
+        .map(|(key, value, _)| (key, value))
+        .collect();
+    let script = render_launch_script(format, Path::new(&request.exe_path), &state.config_paths.root, &env)?;
+
+    if let Some(path) = output_path {
+        std::fs::write(&path, &script)
+            .map_err(|e| format!("Failed to write launch script: {}", e))?;
+
+        #[cfg(unix)]
+        if format == ScriptFormat::Sh {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))
+                .map_err(|e| format!("Failed to make launch script executable: {}", e))?;
+        }
+    }
+
+    Ok(script)
+}
@@ -4,3 +4,4 @@ pub mod packages;
 pub mod presets;
 pub mod launch;
 pub mod settings;
+pub mod watch;
@@ -1,5 +1,24 @@
-use crate::models::Package;
+use crate::commands::houdini::{installed_versions, resolve_version};
+use crate::commands::presets::{preset_priority, rename_package_in_presets};
+use crate::models::{
+    ConfigPaths, EnvConflict, HoudiniVersion, LintFinding, LoadPlan, Package, PackageDiagnostics, PackageLoadStatus,
+    PackageMetadata, PackageSource, PackageSourcesFile, PackageStatesFile,
+};
+use crate::utils::config::{
+    ensure_config_dir, get_package_sources_json_path, get_package_states_json_path, load_json_file,
+    load_json_file_checked, normalize_path, save_json_file,
+};
+use crate::utils::conflicts::{find_env_conflicts, prioritized_load_order};
+use crate::utils::dependencies::resolve_dependencies;
+use crate::utils::expand::Expander;
+use crate::utils::expression::ExprContext;
+use crate::utils::graph::{build_package_graph, render_dot, GraphFormat};
+use crate::utils::install;
+use crate::utils::json_edit::{parse_lenient, set_top_level_key};
+use crate::utils::lint::lint_package;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use tauri::State;
 use crate::AppState;
 
@@ -10,82 +29,543 @@ struct FavoritesFile {
 
 #[tauri::command]
 pub fn load_packages(state: State<AppState>) -> Result<Vec<Package>, String> {
-    let packages_dir = &state.config_paths.packages_dir;
-    if !packages_dir.exists() {
-        return Ok(Vec::new());
+    Ok(scan_packages(&state.config_paths))
+}
+
+/// The configured package directories, or the config root's `packages`
+/// folder when none are configured.
+pub fn package_sources(config_paths: &ConfigPaths) -> Vec<PackageSource> {
+    let configured = load_json_file::<PackageSourcesFile>(&get_package_sources_json_path())
+        .map(|f| f.sources)
+        .unwrap_or_default();
+    if !configured.is_empty() {
+        return configured;
     }
+    vec![PackageSource {
+        name: "default".to_string(),
+        path: config_paths.packages_dir.clone(),
+        max_depth: 0,
+        priority: 0,
+    }]
+}
 
-    let mut packages: Vec<Package> = Vec::new();
+/// Sidecar files holding launcher metadata; they are not packages themselves.
+const METADATA_SUFFIX: &str = ".launcher.json";
+
+fn sidecar_path(package_file: &Path, name: &str) -> PathBuf {
+    package_file.with_file_name(format!("{}{}", name, METADATA_SUFFIX))
+}
 
-    let entries = match std::fs::read_dir(packages_dir) {
+fn collect_package_files(dir: &Path, depth: usize, max_depth: usize, out: &mut Vec<PathBuf>) {
+    let entries = match std::fs::read_dir(dir) {
         Ok(e) => e,
-        Err(_) => return Ok(Vec::new()),
+        Err(_) => return,
     };
 
     for entry in entries.flatten() {
         let path = entry.path();
-        if path.extension().and_then(|e| e.to_str()) == Some("json") {
-            match std::fs::read_to_string(&path) {
-                Ok(content) => {
-                    match serde_json::from_str::<serde_json::Value>(&content) {
-                        Ok(data) => {
-                            let pkg = Package::from_json(
-                                path.file_stem()
-                                    .unwrap_or_default()
-                                    .to_string_lossy()
-                                    .to_string(),
-                                path,
-                                data,
-                                state.config_paths.root.clone(),
-                            );
-                            packages.push(pkg);
-                        }
-                        Err(e) => {
-                            eprintln!("Failed to parse {}: {}", path.display(), e);
-                        }
-                    }
-                }
-                Err(e) => {
-                    eprintln!("Failed to read {}: {}", path.display(), e);
-                }
+        if path.is_dir() {
+            let hidden = entry.file_name().to_string_lossy().starts_with('.');
+            if depth < max_depth && !hidden {
+                collect_package_files(&path, depth + 1, max_depth, out);
+            }
+        } else if path.extension().and_then(|e| e.to_str()) == Some("json")
+            && !path.to_string_lossy().ends_with(METADATA_SUFFIX)
+        {
+            out.push(path);
+        }
+    }
+}
+
+fn read_package(path: &Path, config_root: &Path, source: &str) -> Option<Package> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", path.display(), e);
+            return None;
+        }
+    };
+    match parse_lenient(&content) {
+        Ok(data) => {
+            let mut pkg = Package::from_json(
+                path.file_stem()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .to_string(),
+                path.to_path_buf(),
+                data,
+                config_root.to_path_buf(),
+            );
+            pkg.source = source.to_string();
+
+            let sidecar = sidecar_path(path, &pkg.name);
+            if let Some(data) = std::fs::read_to_string(&sidecar).ok().and_then(|c| parse_lenient(&c).ok()) {
+                let base_dir = path.parent().unwrap_or(Path::new("."));
+                pkg.metadata.merge(PackageMetadata::from_value(&data, base_dir));
+            }
+            Some(pkg)
+        }
+        Err(e) => {
+            eprintln!("Failed to parse {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+fn find_package(config_paths: &ConfigPaths, name: &str) -> Result<Package, String> {
+    scan_packages(config_paths)
+        .into_iter()
+        .find(|p| p.name == name)
+        .ok_or_else(|| format!("Package not found: {}", name))
+}
+
+/// Reads every package from every source. When two sources provide a package
+/// with the same name, the higher-priority source wins (the earlier one on a
+/// tie) and the other files are listed in `shadows`.
+pub fn scan_packages(config_paths: &ConfigPaths) -> Vec<Package> {
+    let states: PackageStatesFile = load_json_file(&get_package_states_json_path()).unwrap_or_default();
+
+    let mut sources = package_sources(config_paths);
+    sources.sort_by_key(|s| std::cmp::Reverse(s.priority));
+
+    let mut packages: Vec<Package> = Vec::new();
+    for source in &sources {
+        let mut files = Vec::new();
+        collect_package_files(&source.path, 0, source.max_depth, &mut files);
+        files.sort();
+
+        for path in files {
+            let Some(mut pkg) = read_package(&path, &config_paths.root, &source.name) else {
+                continue;
+            };
+            if let Some(existing) = packages.iter_mut().find(|p| p.name == pkg.name) {
+                existing.shadows.push(pkg.file_path);
+                continue;
+            }
+            if let Some(&enabled) = states.enabled.get(&pkg.name) {
+                pkg.enabled = enabled;
             }
+            packages.push(pkg);
         }
     }
 
     packages.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
-    Ok(packages)
+    packages
+}
+
+#[tauri::command]
+pub fn list_package_sources(state: State<AppState>) -> Result<Vec<PackageSource>, String> {
+    Ok(package_sources(&state.config_paths))
+}
+
+#[tauri::command]
+pub fn save_package_sources(sources: Vec<PackageSource>) -> Result<(), String> {
+    let sources = sources.into_iter()
+        .map(|s| PackageSource {
+            path: normalize_path(s.path),
+            ..s
+        })
+        .collect();
+
+    ensure_config_dir();
+    save_json_file(&get_package_sources_json_path(), &PackageSourcesFile { sources })
+        .map_err(|e| format!("Failed to save package sources: {}", e))
+}
+
+/// Groups package names by metadata tag, for filtering the package list.
+#[tauri::command]
+pub fn get_package_tags(state: State<AppState>) -> Result<BTreeMap<String, Vec<String>>, String> {
+    let mut tags: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for pkg in scan_packages(&state.config_paths) {
+        for tag in &pkg.metadata.tags {
+            tags.entry(tag.clone()).or_default().push(pkg.name.clone());
+        }
+    }
+    Ok(tags)
+}
+
+/// Reports, for the given Houdini build, whether each package will load.
+#[tauri::command]
+pub fn check_package_enable(houdini: String, state: State<AppState>) -> Result<Vec<PackageLoadStatus>, String> {
+    let versions = installed_versions(&state);
+    let version = resolve_version(&houdini, &versions)
+        .ok_or_else(|| format!("No installed Houdini version matches '{}'", houdini))?;
+    let ctx = ExprContext::for_version(version);
+
+    Ok(scan_packages(&state.config_paths)
+        .iter()
+        .map(|pkg| pkg.load_status(&ctx))
+        .collect())
+}
+
+/// An expander holding the variables of every enabled package, applied in
+/// Houdini's load order.
+fn enabled_packages_expander(
+    packages: &[Package],
+    priority: &[String],
+    version: Option<&HoudiniVersion>,
+    config_root: &Path,
+    ctx: Option<&ExprContext>,
+) -> Expander {
+    let enabled: Vec<&Package> = packages.iter().filter(|p| p.enabled).collect();
+    let mut expander = Expander::for_houdini(version, config_root);
+    for pkg in prioritized_load_order(&enabled, priority) {
+        expander.define_package(pkg, ctx);
+    }
+    expander
+}
+
+/// Lists every unusable path of every package. Variables are expanded across
+/// all enabled packages, in the given preset's priority order, and against the
+/// given Houdini build when there is one.
+#[tauri::command]
+pub fn get_package_diagnostics(
+    houdini: Option<String>,
+    preset: Option<String>,
+    state: State<AppState>,
+) -> Result<Vec<PackageDiagnostics>, String> {
+    let priority = preset_priority(&state.config_paths.presets_file, preset.as_deref())?;
+    let versions = installed_versions(&state);
+    let version = houdini.as_deref().and_then(|h| resolve_version(h, &versions));
+    let ctx = version.map(ExprContext::for_version);
+
+    let packages = scan_packages(&state.config_paths);
+    let expander = enabled_packages_expander(&packages, &priority, version, &state.config_paths.root, ctx.as_ref());
+
+    Ok(packages.iter()
+        .map(|pkg| PackageDiagnostics {
+            name: pkg.name.clone(),
+            issues: pkg.path_issues(&expander, ctx.as_ref()),
+        })
+        .collect())
+}
+
+/// Runs the package linter over every package.
+#[tauri::command]
+pub fn lint_packages(state: State<AppState>) -> Result<Vec<LintFinding>, String> {
+    Ok(scan_packages(&state.config_paths)
+        .iter()
+        .flat_map(|pkg| lint_package(pkg, &state.config_paths.root))
+        .collect())
+}
+
+/// Exports how packages relate, as Graphviz DOT or as JSON nodes and edges.
+/// `enable` expressions are evaluated against the given Houdini build.
+#[tauri::command]
+pub fn export_package_graph(
+    format: GraphFormat,
+    houdini: Option<String>,
+    state: State<AppState>,
+) -> Result<String, String> {
+    let versions = installed_versions(&state);
+    let version = houdini.as_deref().and_then(|h| resolve_version(h, &versions));
+    let ctx = version.map(ExprContext::for_version);
+
+    let graph = build_package_graph(&scan_packages(&state.config_paths), ctx.as_ref());
+    match format {
+        GraphFormat::Dot => Ok(render_dot(&graph)),
+        GraphFormat::Json => serde_json::to_string_pretty(&graph)
+            .map_err(|e| format!("Failed to serialize graph: {}", e)),
+    }
+}
+
+/// Lists variables that more than one enabled package sets where a later
+/// `replace` discards an earlier value, in the given preset's priority order.
+#[tauri::command]
+pub fn get_env_conflicts(
+    houdini: Option<String>,
+    preset: Option<String>,
+    state: State<AppState>,
+) -> Result<Vec<EnvConflict>, String> {
+    let priority = preset_priority(&state.config_paths.presets_file, preset.as_deref())?;
+    let versions = installed_versions(&state);
+    let version = houdini.as_deref().and_then(|h| resolve_version(h, &versions));
+    let ctx = version.map(ExprContext::for_version);
+
+    let packages = scan_packages(&state.config_paths);
+    let expander = enabled_packages_expander(&packages, &priority, version, &state.config_paths.root, ctx.as_ref());
+    let enabled: Vec<&Package> = packages.iter().filter(|p| p.enabled).collect();
+    Ok(find_env_conflicts(&enabled, &priority, &expander, ctx.as_ref()))
+}
+
+/// Expands a preset's package list with everything it `requires` and flags
+/// missing or disabled dependencies and cycles.
+#[tauri::command]
+pub fn resolve_preset_packages(
+    packages: Vec<String>,
+    houdini: Option<String>,
+    state: State<AppState>,
+) -> Result<LoadPlan, String> {
+    let versions = installed_versions(&state);
+    let ctx = houdini.as_deref()
+        .and_then(|h| resolve_version(h, &versions))
+        .map(ExprContext::for_version);
+
+    let available = scan_packages(&state.config_paths);
+    Ok(resolve_dependencies(&packages, &available, ctx.as_ref()))
 }
 
+/// Records the enabled state for the current user only; the shared package
+/// files are never modified.
 #[tauri::command]
 pub fn save_package_enabled(
     package_name: String,
     enabled: bool,
     state: State<AppState>,
 ) -> Result<(), String> {
-    let packages_dir = &state.config_paths.packages_dir;
-    let pkg_path = packages_dir.join(&format!("{}.json", package_name));
+    if !scan_packages(&state.config_paths).iter().any(|p| p.name == package_name) {
+        return Err(format!("Package not found: {}", package_name));
+    }
+
+    edit_package_states(|states| {
+        states.enabled.insert(package_name, enabled);
+    })
+}
+
+/// Sets one top-level key in a package file, leaving the rest of the file,
+/// comments and formatting included, exactly as it was.
+#[tauri::command]
+pub fn update_package_key(
+    package_name: String,
+    key: String,
+    value: serde_json::Value,
+    state: State<AppState>,
+) -> Result<Package, String> {
+    let pkg = find_package(&state.config_paths, &package_name)?;
+
+    let content = std::fs::read_to_string(&pkg.file_path)
+        .map_err(|e| format!("Failed to read package: {}", e))?;
+    let updated = set_top_level_key(&content, &key, &value)
+        .map_err(|e| format!("Failed to update package: {}", e))?;
+    std::fs::write(&pkg.file_path, updated)
+        .map_err(|e| format!("Failed to write package: {}", e))?;
 
-    if !pkg_path.exists() {
-        return Err(format!("Package file not found: {}", pkg_path.display()));
+    read_package(&pkg.file_path, &pkg.config_root, &pkg.source)
+        .ok_or_else(|| format!("Package is no longer valid: {}", pkg.file_path.display()))
+}
+
+/// Installs a package from a local `.zip` archive or folder into the managed
+/// payload dir and writes its package file to `packages_dir`, which must be one
+/// of the package sources so the package is loaded.
+#[tauri::command]
+pub fn install_package(
+    source: String,
+    name: Option<String>,
+    overwrite: bool,
+    state: State<AppState>,
+) -> Result<Package, String> {
+    let config_paths = &state.config_paths;
+    let packages_dir = normalize_path(config_paths.packages_dir.clone());
+    let target = package_sources(config_paths)
+        .into_iter()
+        .find(|s| normalize_path(s.path.clone()) == packages_dir)
+        .ok_or_else(|| format!(
+            "Cannot install: {} is not one of the configured package sources",
+            config_paths.packages_dir.display()
+        ))?;
+
+    let package_file = install::install_package(
+        &normalize_path(PathBuf::from(source)),
+        name.as_deref(),
+        overwrite,
+        config_paths,
+    )?;
+    read_package(&package_file, &config_paths.root, &target.name)
+        .ok_or_else(|| format!("Package is no longer valid: {}", package_file.display()))
+}
+
+fn edit_package_states(edit: impl FnOnce(&mut PackageStatesFile)) -> Result<(), String> {
+    let path = get_package_states_json_path();
+    let mut states: PackageStatesFile = load_json_file_checked(&path)?.unwrap_or_default();
+    edit(&mut states);
+    ensure_config_dir();
+    save_json_file(&path, &states)
+        .map_err(|e| format!("Failed to save package state: {}", e))
+}
+
+fn edit_favorites(path: &Path, edit: impl FnOnce(&mut Vec<String>)) -> Result<(), String> {
+    let Some(mut data) = load_json_file::<FavoritesFile>(path) else {
+        return Ok(());
+    };
+    edit(&mut data.favorites);
+    save_json_file(path, &data)
+        .map_err(|e| format!("Failed to write favorites: {}", e))
+}
+
+/// Checks that `name` can be used for a new package file next to `package_file`.
+fn check_new_name(config_paths: &ConfigPaths, package_file: &Path, name: &str) -> Result<PathBuf, String> {
+    if !install::is_valid_package_name(name) {
+        return Err(format!("Invalid package name: {}", name));
     }
+    let target = package_file.with_file_name(format!("{}.json", name));
+    if target.exists() || scan_packages(config_paths).iter().any(|p| p.name == name) {
+        return Err(format!("Package already exists: {}", name));
+    }
+    Ok(target)
+}
 
-    match std::fs::read_to_string(&pkg_path) {
-        Ok(content) => {
-            let mut data: serde_json::Value = serde_json::from_str(&content)
-                .map_err(|e| format!("Failed to parse package JSON: {}", e))?;
+/// Deletes a package file and its metadata sidecar. A payload folder is only
+/// removed when the launcher installed the package and no other package uses
+/// it.
+#[tauri::command]
+pub fn delete_package(package_name: String, state: State<AppState>) -> Result<(), String> {
+    let config_paths = &state.config_paths;
+    let pkg = find_package(config_paths, &package_name)?;
+    // A duplicate may still use the payload; it is kept for it then.
+    let payload = install::managed_payload_dir(&pkg.name, &pkg.file_path, config_paths)
+        .filter(|_| !payload_shared(config_paths, &pkg));
 
-            data["enable"] = serde_json::Value::Bool(enabled);
+    std::fs::remove_file(&pkg.file_path)
+        .map_err(|e| format!("Failed to delete package: {}", e))?;
+    std::fs::remove_file(sidecar_path(&pkg.file_path, &pkg.name)).ok();
+    if let Some(dir) = payload {
+        std::fs::remove_dir_all(&dir)
+            .map_err(|e| format!("Failed to delete package payload: {}", e))?;
+    }
+
+    edit_favorites(&config_paths.favorites_file, |favorites| favorites.retain(|f| *f != package_name))?;
+    edit_package_states(|states| {
+        states.enabled.remove(&package_name);
+    })
+}
+
+/// Whether another package file points at `pkg`'s managed payload, as copies
+/// made by `duplicate_package` do.
+fn payload_shared(config_paths: &ConfigPaths, pkg: &Package) -> bool {
+    scan_packages(config_paths).iter().any(|other| {
+        std::iter::once(&other.file_path)
+            .chain(&other.shadows)
+            .filter(|file| **file != pkg.file_path)
+            .any(|file| std::fs::read_to_string(file)
+                .is_ok_and(|c| install::references_payload(&c, &pkg.name, config_paths)))
+    })
+}
+
+/// Points favorites and the per-user enabled state at a renamed package.
+fn rename_package_in_user_files(config_paths: &ConfigPaths, old: &str, new: &str) -> Result<(), String> {
+    edit_package_states(|states| {
+        if let Some(enabled) = states.enabled.remove(old) {
+            states.enabled.insert(new.to_string(), enabled);
+        }
+    })?;
+    edit_favorites(&config_paths.favorites_file, |favorites| {
+        for f in favorites.iter_mut().filter(|f| **f == old) {
+            *f = new.to_string();
+        }
+    })
+}
+
+/// Renames a package file, its sidecar and managed payload, and updates the
+/// presets, favorites and enabled state that refer to it. If a step fails the
+/// earlier ones are undone, and the old file is only removed once everything
+/// else has been renamed.
+#[tauri::command]
+pub fn rename_package(
+    package_name: String,
+    new_name: String,
+    state: State<AppState>,
+) -> Result<Package, String> {
+    let config_paths = &state.config_paths;
+    let pkg = find_package(config_paths, &package_name)?;
+    let new_file = check_new_name(config_paths, &pkg.file_path, &new_name)?;
+
+    let mut content = std::fs::read_to_string(&pkg.file_path)
+        .map_err(|e| format!("Failed to read package: {}", e))?;
+    // A payload shared with a duplicate stays where it is, under the old name,
+    // so both packages keep finding it.
+    let payload = install::managed_payload_dir(&pkg.name, &pkg.file_path, config_paths)
+        .filter(|_| !payload_shared(config_paths, &pkg));
+    let payload_move = match payload {
+        Some(dir) => {
+            let new_dir = config_paths.managed_packages_dir.join(&new_name);
+            if new_dir.exists() {
+                return Err(format!("Package payload already exists: {}", new_dir.display()));
+            }
+            content = install::replace_payload_ref(&content, &pkg.name, &new_name, config_paths);
+            Some((dir, new_dir))
+        }
+        None => None,
+    };
+    let sidecar = sidecar_path(&pkg.file_path, &pkg.name);
+    let new_sidecar = sidecar_path(&new_file, &new_name);
+
+    let mut undo: Vec<Box<dyn FnOnce() + '_>> = Vec::new();
+    let result = (|| {
+        std::fs::write(&new_file, content)
+            .map_err(|e| format!("Failed to write package: {}", e))?;
+        undo.push(Box::new(|| {
+            std::fs::remove_file(&new_file).ok();
+        }));
+
+        if let Some((dir, new_dir)) = &payload_move {
+            std::fs::rename(dir, new_dir)
+                .map_err(|e| format!("Failed to rename package payload: {}", e))?;
+            undo.push(Box::new(move || {
+                std::fs::rename(new_dir, dir).ok();
+            }));
+        }
 
-            let new_content = serde_json::to_string_pretty(&data)
-                .map_err(|e| format!("Failed to serialize package: {}", e))?;
+        if sidecar.exists() {
+            std::fs::rename(&sidecar, &new_sidecar)
+                .map_err(|e| format!("Failed to rename package metadata: {}", e))?;
+            undo.push(Box::new(|| {
+                std::fs::rename(&new_sidecar, &sidecar).ok();
+            }));
+        }
+
+        rename_package_in_presets(&config_paths.presets_file, &package_name, &new_name)?;
+        undo.push(Box::new(|| {
+            rename_package_in_presets(&config_paths.presets_file, &new_name, &package_name).ok();
+        }));
 
-            std::fs::write(&pkg_path, new_content)
-                .map_err(|e| format!("Failed to write package: {}", e))?;
+        rename_package_in_user_files(config_paths, &package_name, &new_name)?;
+        undo.push(Box::new(|| {
+            rename_package_in_user_files(config_paths, &new_name, &package_name).ok();
+        }));
 
-            Ok(())
+        std::fs::remove_file(&pkg.file_path)
+            .map_err(|e| format!("Failed to remove old package file: {}", e))
+    })();
+
+    if let Err(e) = result {
+        for step in undo.into_iter().rev() {
+            step();
         }
-        Err(e) => Err(format!("Failed to read package: {}", e)),
+        return Err(e);
+    }
+
+    read_package(&new_file, &pkg.config_root, &pkg.source)
+        .ok_or_else(|| format!("Package is no longer valid: {}", new_file.display()))
+}
+
+/// Copies a package file and its sidecar under a new name, as a starting
+/// point for a variant. The copy shares the original's payload and starts
+/// disabled for this user so the two are not loaded together.
+#[tauri::command]
+pub fn duplicate_package(
+    package_name: String,
+    new_name: String,
+    state: State<AppState>,
+) -> Result<Package, String> {
+    let config_paths = &state.config_paths;
+    let pkg = find_package(config_paths, &package_name)?;
+    let new_file = check_new_name(config_paths, &pkg.file_path, &new_name)?;
+
+    std::fs::copy(&pkg.file_path, &new_file)
+        .map_err(|e| format!("Failed to copy package: {}", e))?;
+    let sidecar = sidecar_path(&pkg.file_path, &pkg.name);
+    if sidecar.exists() {
+        std::fs::copy(&sidecar, sidecar_path(&new_file, &new_name))
+            .map_err(|e| format!("Failed to copy package metadata: {}", e))?;
     }
+    edit_package_states(|states| {
+        states.enabled.insert(new_name.clone(), false);
+    })?;
+
+    let mut copy = read_package(&new_file, &pkg.config_root, &pkg.source)
+        .ok_or_else(|| format!("Package is no longer valid: {}", new_file.display()))?;
+    copy.enabled = false;
+    Ok(copy)
 }
 
 #[tauri::command]
@@ -1,6 +1,7 @@
 use crate::models::{PresetData, PresetsFile};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::Path;
 use tauri::State;
 use crate::AppState;
 
@@ -16,6 +17,9 @@ struct PresetSaveData {
     houdini: String,
     avatar: Option<String>,
     avatar_path: Option<String>,
+    executable: Option<String>,
+    #[serde(default)]
+    priority: Option<Vec<String>>,
 }
 
 #[tauri::command]
@@ -54,6 +58,8 @@ pub fn save_presets(
             houdini: preset.houdini.clone(),
             avatar: if preset.avatar.is_empty() { None } else { Some(preset.avatar.clone()) },
             avatar_path: if preset.avatar_path.is_empty() { None } else { Some(preset.avatar_path.clone()) },
+            executable: if preset.executable.is_empty() { None } else { Some(preset.executable.clone()) },
+            priority: if preset.priority.is_empty() { None } else { Some(preset.priority.clone()) },
         });
     }
 
@@ -81,6 +87,7 @@ pub fn create_preset(
     name: String,
     packages: Vec<String>,
     houdini: String,
+    executable: Option<String>,
     _state: State<AppState>,
 ) -> Result<PresetData, String> {
     Ok(PresetData {
@@ -89,6 +96,8 @@ pub fn create_preset(
         houdini,
         avatar: String::new(),
         avatar_path: String::new(),
+        executable: executable.unwrap_or_default(),
+        priority: Vec::new(),
     })
 }
 
@@ -105,3 +114,67 @@ pub fn set_default_preset(
 ) -> Result<(), String> {
     Ok(())
 }
+
+/// The `priority` list of the named preset, or an empty list when no preset is
+/// given. Reads the presets file in either of its shapes.
+pub fn preset_priority(path: &Path, preset: Option<&str>) -> Result<Vec<String>, String> {
+    let Some(name) = preset else {
+        return Ok(Vec::new());
+    };
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read presets: {}", e))?;
+    let data: serde_json::Value = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse presets: {}", e))?;
+
+    let found = match data.get("presets") {
+        Some(serde_json::Value::Object(map)) => map.get(name),
+        Some(serde_json::Value::Array(list)) => list.iter()
+            .find(|p| p.get("name").and_then(|n| n.as_str()) == Some(name)),
+        _ => None,
+    };
+    let preset = found.ok_or_else(|| format!("Preset not found: {}", name))?;
+    Ok(preset.get("priority")
+        .and_then(|p| serde_json::from_value(p.clone()).ok())
+        .unwrap_or_default())
+}
+
+/// Replaces `old` with `new` in the package and priority lists of every
+/// preset in the presets file. Works on the raw JSON so fields the launcher does not know
+/// about are kept.
+pub fn rename_package_in_presets(path: &Path, old: &str, new: &str) -> Result<(), String> {
+    if !path.exists() {
+        return Ok(());
+    }
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read presets: {}", e))?;
+    let mut data: serde_json::Value = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse presets: {}", e))?;
+
+    let presets: Vec<&mut serde_json::Value> = match data.get_mut("presets") {
+        Some(serde_json::Value::Object(map)) => map.values_mut().collect(),
+        Some(serde_json::Value::Array(list)) => list.iter_mut().collect(),
+        _ => Vec::new(),
+    };
+    let mut changed = false;
+    for preset in presets {
+        for list in ["packages", "priority"] {
+            let Some(packages) = preset.get_mut(list).and_then(|p| p.as_array_mut()) else {
+                continue;
+            };
+            for package in packages.iter_mut() {
+                if package.as_str() == Some(old) {
+                    *package = serde_json::Value::String(new.to_string());
+                    changed = true;
+                }
+            }
+        }
+    }
+
+    if !changed {
+        return Ok(());
+    }
+    let content = serde_json::to_string_pretty(&data)
+        .map_err(|e| format!("Failed to serialize presets: {}", e))?;
+    std::fs::write(path, content)
+        .map_err(|e| format!("Failed to write presets: {}", e))
+}
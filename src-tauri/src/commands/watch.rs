@@ -0,0 +1,177 @@
+use crate::commands::packages::{package_sources, scan_packages};
+use crate::models::{ChangeSet, ConfigPaths};
+use crate::utils::config::{get_package_sources_json_path, get_package_states_json_path};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Emitter};
+
+pub const PACKAGES_CHANGED_EVENT: &str = "packages-changed";
+pub const PRESETS_CHANGED_EVENT: &str = "presets-changed";
+pub const FAVORITES_CHANGED_EVENT: &str = "favorites-changed";
+
+/// Package folders often live on network shares where change notifications
+/// are unreliable, so the watcher polls file timestamps instead.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+type FileStamp = (PathBuf, Option<SystemTime>, u64);
+
+fn file_stamp(path: &Path, out: &mut Vec<FileStamp>) {
+    let meta = std::fs::metadata(path).ok();
+    out.push((
+        path.to_path_buf(),
+        meta.as_ref().and_then(|m| m.modified().ok()),
+        meta.map(|m| m.len()).unwrap_or_default(),
+    ));
+}
+
+/// Stamps every JSON file (package files and metadata sidecars) in `dir`, down
+/// to the depth the package scan reads.
+fn dir_stamps(dir: &Path, depth: usize, max_depth: usize, out: &mut Vec<FileStamp>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            if depth < max_depth && !entry.file_name().to_string_lossy().starts_with('.') {
+                dir_stamps(&path, depth + 1, max_depth, out);
+            }
+        } else if path.extension().and_then(|e| e.to_str()) == Some("json") {
+            file_stamp(&path, out);
+        }
+    }
+}
+
+fn package_stamps(config_paths: &ConfigPaths) -> Vec<FileStamp> {
+    let mut stamps = Vec::new();
+    file_stamp(&get_package_sources_json_path(), &mut stamps);
+    file_stamp(&get_package_states_json_path(), &mut stamps);
+    for source in package_sources(config_paths) {
+        dir_stamps(&source.path, 0, source.max_depth, &mut stamps);
+    }
+    stamps.sort();
+    stamps
+}
+
+/// Each package serialized, so any change to what `load_packages` returns is
+/// noticed.
+fn package_snapshot(config_paths: &ConfigPaths) -> BTreeMap<String, String> {
+    scan_packages(config_paths)
+        .into_iter()
+        .map(|p| (p.name.clone(), serde_json::to_string(&p).unwrap_or_default()))
+        .collect()
+}
+
+/// Presets by name. The file may hold them as a map keyed by name or as a
+/// list of objects with a `name` field.
+fn preset_snapshot(path: &Path) -> BTreeMap<String, String> {
+    let data: serde_json::Value = std::fs::read_to_string(path)
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default();
+
+    match data.get("presets") {
+        Some(serde_json::Value::Object(map)) => map.iter()
+            .map(|(name, preset)| (name.clone(), preset.to_string()))
+            .collect(),
+        Some(serde_json::Value::Array(list)) => list.iter()
+            .filter_map(|preset| {
+                let name = preset.get("name")?.as_str()?;
+                Some((name.to_string(), preset.to_string()))
+            })
+            .collect(),
+        _ => BTreeMap::new(),
+    }
+}
+
+fn favorites_snapshot(path: &Path) -> BTreeMap<String, String> {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|c| serde_json::from_str::<serde_json::Value>(&c).ok())
+        .and_then(|data| data.get("favorites").cloned())
+        .and_then(|f| serde_json::from_value::<Vec<String>>(f).ok())
+        .unwrap_or_default()
+        .into_iter()
+        .map(|name| (name, String::new()))
+        .collect()
+}
+
+fn diff(old: &BTreeMap<String, String>, new: &BTreeMap<String, String>) -> ChangeSet {
+    ChangeSet {
+        added: new.keys().filter(|k| !old.contains_key(*k)).cloned().collect(),
+        removed: old.keys().filter(|k| !new.contains_key(*k)).cloned().collect(),
+        changed: new.iter()
+            .filter(|(k, v)| old.get(*k).is_some_and(|o| o != *v))
+            .map(|(k, _)| k.clone())
+            .collect(),
+    }
+}
+
+/// A watched set of files: re-read only when their stamps move, and an event
+/// emitted only when what was read actually differs.
+struct Watched {
+    event: &'static str,
+    stamps: Vec<FileStamp>,
+    snapshot: BTreeMap<String, String>,
+}
+
+impl Watched {
+    fn new(event: &'static str, stamps: Vec<FileStamp>, snapshot: BTreeMap<String, String>) -> Self {
+        Self { event, stamps, snapshot }
+    }
+
+    fn poll(
+        &mut self,
+        app: &AppHandle,
+        stamps: Vec<FileStamp>,
+        read: impl FnOnce() -> BTreeMap<String, String>,
+    ) {
+        if stamps == self.stamps {
+            return;
+        }
+        self.stamps = stamps;
+        let snapshot = read();
+        let changes = diff(&self.snapshot, &snapshot);
+        self.snapshot = snapshot;
+        if !changes.is_empty() {
+            if let Err(e) = app.emit(self.event, changes) {
+                eprintln!("Failed to emit {}: {:?}", self.event, e);
+            }
+        }
+    }
+}
+
+/// Starts a background thread that watches the package sources, the presets
+/// file and the favorites file, emitting a `ChangeSet` event for each when
+/// their contents change.
+pub fn start_watcher(app: AppHandle, config_paths: ConfigPaths) {
+    std::thread::spawn(move || {
+        let stamp = |path: &Path| {
+            let mut stamps = Vec::new();
+            file_stamp(path, &mut stamps);
+            stamps
+        };
+        let presets_file = config_paths.presets_file.clone();
+        let favorites_file = config_paths.favorites_file.clone();
+
+        let mut packages = Watched::new(
+            PACKAGES_CHANGED_EVENT,
+            package_stamps(&config_paths),
+            package_snapshot(&config_paths),
+        );
+        let mut presets = Watched::new(PRESETS_CHANGED_EVENT, stamp(&presets_file), preset_snapshot(&presets_file));
+        let mut favorites = Watched::new(
+            FAVORITES_CHANGED_EVENT,
+            stamp(&favorites_file),
+            favorites_snapshot(&favorites_file),
+        );
+
+        loop {
+            std::thread::sleep(POLL_INTERVAL);
+            packages.poll(&app, package_stamps(&config_paths), || package_snapshot(&config_paths));
+            presets.poll(&app, stamp(&presets_file), || preset_snapshot(&presets_file));
+            favorites.poll(&app, stamp(&favorites_file), || favorites_snapshot(&favorites_file));
+        }
+    });
+}
@@ -6,7 +6,7 @@ mod utils;
 
 use commands::config::get_config_paths;
 use models::{ConfigPaths, Package, PresetData};
-use tauri::WindowEvent;
+use tauri::{Manager, WindowEvent};
 
 pub struct AppState {
     pub config_paths: ConfigPaths,
@@ -43,17 +43,42 @@ fn main() {
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .manage(AppState::new())
+        .setup(|app| {
+            let config_paths = app.state::<AppState>().config_paths.clone();
+            commands::watch::start_watcher(app.handle().clone(), config_paths);
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             get_config_paths,
             commands::config::discover_config_root_cmd,
             commands::config::save_config_root,
             commands::houdini::list_houdini_versions,
+            commands::houdini::resolve_houdini_version,
             commands::houdini::get_houdini_exe_path,
             commands::houdini::save_houdini_exe,
             commands::houdini::load_saved_houdini_exe,
+            commands::houdini::load_saved_houdini_root,
+            commands::houdini::save_houdini_root,
+            commands::houdini::list_houdini_roots,
+            commands::houdini::add_houdini_root,
+            commands::houdini::remove_houdini_root,
             commands::packages::load_packages,
             commands::packages::save_package_enabled,
+            commands::packages::update_package_key,
+            commands::packages::install_package,
+            commands::packages::delete_package,
+            commands::packages::rename_package,
+            commands::packages::duplicate_package,
             commands::packages::get_packages_list,
+            commands::packages::get_package_tags,
+            commands::packages::list_package_sources,
+            commands::packages::save_package_sources,
+            commands::packages::check_package_enable,
+            commands::packages::get_package_diagnostics,
+            commands::packages::lint_packages,
+            commands::packages::resolve_preset_packages,
+            commands::packages::get_env_conflicts,
+            commands::packages::export_package_graph,
             commands::packages::load_favorites,
             commands::packages::save_favorites,
             commands::presets::load_presets,
@@ -62,6 +87,8 @@ fn main() {
             commands::presets::delete_preset,
             commands::presets::set_default_preset,
             commands::launch::launch_houdini,
+            commands::launch::preview_launch_environment,
+            commands::launch::export_launch_script,
             commands::settings::is_autostart_enabled,
             commands::settings::set_autostart,
             commands::settings::is_deadline_monitor_enabled,
@@ -1,10 +1,17 @@
+mod package_spec;
+
+pub use package_spec::{EnvMethod, EnvValue, PackageEnable, PackageSpec};
+
+use crate::utils::expand::{split_search_path, Expander};
+use crate::utils::expression::{evaluate, ExprContext};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConfigPaths {
     pub root: PathBuf,
     pub packages_dir: PathBuf,
+    pub managed_packages_dir: PathBuf,
     pub global_vars_file: PathBuf,
     pub presets_file: PathBuf,
     pub favorites_file: PathBuf,
@@ -18,6 +25,7 @@ impl ConfigPaths {
         Self {
             root: root.clone(),
             packages_dir: root.join("packages"),
+            managed_packages_dir: root.join("package_payloads"),
             global_vars_file: root.join("scripts").join("global_vars.json"),
             presets_file: root.join("config").join("launcher_presets.json"),
             favorites_file: root.join("config").join("launcher_favorites.json"),
@@ -27,76 +35,371 @@ impl ConfigPaths {
     }
 }
 
+/// Descriptive fields for the launcher UI, read from a `"launcher"` block in
+/// the package file or from a `<name>.launcher.json` sidecar next to it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PackageMetadata {
+    pub description: Option<String>,
+    pub version: Option<String>,
+    pub author: Option<String>,
+    pub tags: Vec<String>,
+    pub icon: Option<PathBuf>,
+    pub homepage: Option<String>,
+}
+
+impl PackageMetadata {
+    /// Reads the known fields from `data`; a relative icon path is resolved
+    /// against `base_dir`.
+    pub fn from_value(data: &serde_json::Value, base_dir: &Path) -> Self {
+        let text = |key: &str| -> Option<String> {
+            data.get(key)
+                .and_then(|v| match v {
+                    serde_json::Value::String(s) => Some(s.trim().to_string()),
+                    serde_json::Value::Number(n) => Some(n.to_string()),
+                    _ => None,
+                })
+                .filter(|s| !s.is_empty())
+        };
+
+        let tags = match data.get("tags") {
+            Some(serde_json::Value::String(s)) => s.split(',').map(|t| t.trim().to_string()).collect(),
+            Some(serde_json::Value::Array(arr)) => arr.iter()
+                .filter_map(|t| t.as_str().map(|t| t.trim().to_string()))
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        Self {
+            description: text("description"),
+            version: text("version"),
+            author: text("author"),
+            tags: tags.into_iter().filter(|t: &String| !t.is_empty()).collect(),
+            icon: text("icon").map(|icon| base_dir.join(icon)),
+            homepage: text("homepage"),
+        }
+    }
+
+    /// Fills in fields from `other`, which takes precedence where it has a value.
+    pub fn merge(&mut self, other: PackageMetadata) {
+        self.description = other.description.or(self.description.take());
+        self.version = other.version.or(self.version.take());
+        self.author = other.author.or(self.author.take());
+        if !other.tags.is_empty() {
+            self.tags = other.tags;
+        }
+        self.icon = other.icon.or(self.icon.take());
+        self.homepage = other.homepage.or(self.homepage.take());
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Package {
     pub name: String,
     pub file_path: PathBuf,
     pub data: serde_json::Value,
+    pub spec: PackageSpec,
+    pub metadata: PackageMetadata,
     pub enabled: bool,
     pub config_root: PathBuf,
+    pub source: String,
+    pub shadows: Vec<PathBuf>,
 }
 
 impl Package {
     pub fn from_json(name: String, file_path: PathBuf, data: serde_json::Value, config_root: PathBuf) -> Self {
-        let enabled = data.get("enable")
-            .and_then(|v| v.as_bool())
-            .unwrap_or(true);
+        let spec = PackageSpec::from_value(&data);
+        let base_dir = file_path.parent().map(|p| p.to_path_buf()).unwrap_or_default();
+        let metadata = data.get("launcher")
+            .map(|block| PackageMetadata::from_value(block, &base_dir))
+            .unwrap_or_default();
+        let enabled = match &spec.enable {
+            PackageEnable::Bool(b) => *b,
+            PackageEnable::Expr(_) => true,
+        };
         Self {
             name,
             file_path,
             data,
+            spec,
+            metadata,
             enabled,
             config_root,
+            source: String::new(),
+            shadows: Vec::new(),
         }
     }
 
-    pub fn has_missing_paths(&self) -> bool {
-        if let Some(env) = self.data.get("env") {
-            if let Some(arr) = env.as_array() {
-                for entry in arr {
-                    if let Some(obj) = entry.as_object() {
-                        for (key, value) in obj {
-                            if key == "hpath" || key == "path" {
-                                let path_str = value.as_str().unwrap_or("");
-                                let resolved = self.resolve_path(path_str);
-                                if !resolved.exists() {
-                                    return true;
-                                }
-                            }
-                        }
-                    }
-                }
-            }
+    /// Whether the package's `enable` expression lets it load. A plain boolean
+    /// is the default for `enabled` and is overridden when packages are staged,
+    /// so only expressions are checked. Without a build to evaluate against,
+    /// expressions are assumed to pass.
+    pub fn spec_allows(&self, ctx: Option<&ExprContext>) -> bool {
+        match (&self.spec.enable, ctx) {
+            (PackageEnable::Expr(expr), Some(ctx)) => evaluate(expr, ctx).unwrap_or(false),
+            _ => true,
         }
-        false
     }
 
-    fn resolve_path(&self, raw: &str) -> PathBuf {
-        let mut resolved = raw.to_string();
-        let env_vars = self.get_env_map();
-        for (key, val) in env_vars {
-            resolved = resolved.replace(&format!("${}", key), &val);
+    /// Whether Houdini will load this package for the given build, and why.
+    pub fn load_status(&self, ctx: &ExprContext) -> PackageLoadStatus {
+        let (loads, reason) = if !self.enabled {
+            (false, "Disabled in the launcher".to_string())
+        } else {
+            match &self.spec.enable {
+                PackageEnable::Bool(_) => (true, "Enabled".to_string()),
+                PackageEnable::Expr(expr) => match evaluate(expr, ctx) {
+                    Ok(true) => (true, format!("\"{}\" is true", expr)),
+                    Ok(false) => (false, format!("\"{}\" is false", expr)),
+                    Err(e) => (false, format!("Invalid enable expression: {}", e)),
+                },
+            }
+        };
+        PackageLoadStatus {
+            name: self.name.clone(),
+            loads,
+            reason,
         }
-        resolved = resolved.replace("$CONFIG_ROOT_PATH", &self.config_root.to_string_lossy());
-        PathBuf::from(resolved)
-    }
-
-    fn get_env_map(&self) -> std::collections::HashMap<String, String> {
-        let mut env = std::collections::HashMap::new();
-        if let Some(env_arr) = self.data.get("env").and_then(|v| v.as_array()) {
-            for entry in env_arr {
-                if let Some(obj) = entry.as_object() {
-                    for (key, val) in obj {
-                        match val {
-                            serde_json::Value::String(s) => { env.insert(key.clone(), s.clone()); }
-                            serde_json::Value::Number(n) => { env.insert(key.clone(), n.to_string()); }
-                            _ => {}
-                        }
-                    }
+    }
+
+    /// Folder-valued entries as (key, value): `hpath`/`path`, including ones
+    /// declared inside `env`, and `package_path`. Conditional values are kept
+    /// only when `ctx` is given and their condition holds.
+    fn path_values(&self, ctx: Option<&ExprContext>) -> Vec<(&str, &EnvValue)> {
+        let env_paths = self.spec.env.iter()
+            .filter(|e| e.key == "hpath" || e.key == "path")
+            .flat_map(|e| e.values.iter().map(move |v| (e.key.as_str(), v)));
+        self.spec.hpath.iter().map(|v| ("hpath", v))
+            .chain(env_paths)
+            .chain(self.spec.package_path.iter().map(|v| ("package_path", v)))
+            .filter(|(_, v)| v.applies(ctx))
+            .collect()
+    }
+
+    /// Every folder this package points at that Houdini would fail to use.
+    pub fn path_issues(&self, expander: &Expander, ctx: Option<&ExprContext>) -> Vec<PathIssue> {
+        let mut issues = Vec::new();
+
+        for (key, value) in self.path_values(ctx) {
+            let expanded = match expander.expand(&value.value) {
+                Ok(expanded) => expanded,
+                Err(e) => {
+                    issues.push(PathIssue {
+                        key: key.to_string(),
+                        raw: value.value.clone(),
+                        expanded: value.value.clone(),
+                        problem: PathProblem::Unresolved,
+                        detail: Some(e),
+                    });
+                    continue;
+                }
+            };
+
+            // A value may hold a whole search path; "&" stands for Houdini's defaults.
+            for part in split_search_path(&expanded) {
+                if part == "&" {
+                    continue;
+                }
+                if let Some((problem, detail)) = check_dir(Path::new(part)) {
+                    issues.push(PathIssue {
+                        key: key.to_string(),
+                        raw: value.value.clone(),
+                        expanded: part.to_string(),
+                        problem,
+                        detail,
+                    });
                 }
             }
         }
-        env
+
+        issues
+    }
+
+    pub fn has_missing_paths(&self) -> bool {
+        !self.path_issues(&self.expander(), None).is_empty()
+    }
+
+    /// An expander holding only this package's own variables.
+    fn expander(&self) -> Expander {
+        let mut expander = Expander::for_houdini(None, &self.config_root);
+        expander.define_package(self, None);
+        expander
+    }
+}
+
+fn check_dir(path: &Path) -> Option<(PathProblem, Option<String>)> {
+    if path.to_string_lossy().contains('$') {
+        return Some((PathProblem::Unresolved, Some("Contains an undefined variable".to_string())));
+    }
+    match std::fs::metadata(path) {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Some((PathProblem::Missing, None)),
+        Err(e) => Some((PathProblem::Unreadable, Some(e.to_string()))),
+        Ok(meta) if !meta.is_dir() => Some((PathProblem::NotADirectory, None)),
+        Ok(_) => std::fs::read_dir(path)
+            .err()
+            .map(|e| (PathProblem::Unreadable, Some(e.to_string()))),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PathProblem {
+    Missing,
+    NotADirectory,
+    Unreadable,
+    Unresolved,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathIssue {
+    pub key: String,
+    pub raw: String,
+    pub expanded: String,
+    pub problem: PathProblem,
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageDiagnostics {
+    pub name: String,
+    pub issues: Vec<PathIssue>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LintSeverity {
+    Error,
+    Warning,
+    Info,
+}
+
+/// One lint finding. `pointer` is a JSON pointer (RFC 6901) into the package
+/// file, e.g. `/env/2/PATH`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LintFinding {
+    pub package: String,
+    pub rule: String,
+    pub severity: LintSeverity,
+    pub pointer: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageLoadStatus {
+    pub name: String,
+    pub loads: bool,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyIssueKind {
+    Missing,
+    Disabled,
+    Cycle,
+    /// Other copies of the package exist and it does not set
+    /// `load_package_once`, so Houdini would load them all while the launcher
+    /// loads only one.
+    Duplicate,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DependencyIssue {
+    pub package: String,
+    pub dependency: String,
+    pub kind: DependencyIssueKind,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoadPlan {
+    pub packages: Vec<String>,
+    pub pulled_in: Vec<String>,
+    pub issues: Vec<DependencyIssue>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvContribution {
+    pub package: String,
+    pub value: String,
+    pub method: EnvMethod,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvConflict {
+    pub variable: String,
+    pub contributions: Vec<EnvContribution>,
+    pub winner: String,
+    pub overwritten: Vec<String>,
+    pub final_value: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvSource {
+    pub source: String,
+    pub value: String,
+    pub method: EnvMethod,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvVariable {
+    pub name: String,
+    pub value: String,
+    pub sources: Vec<EnvSource>,
+    /// Why the value could not be worked out, such as a variable cycle.
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphNodeStatus {
+    Enabled,
+    Disabled,
+    /// Named in a `requires` list but not found in any source.
+    Missing,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphNode {
+    pub id: String,
+    pub status: GraphNodeStatus,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GraphEdgeKind {
+    /// `from` lists `to` in `requires`.
+    Requires,
+    /// Both set the same variables; `from` is read first.
+    SharedVariable,
+    /// `from` uses variables that only `to` sets.
+    VariableReference,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphEdge {
+    pub from: String,
+    pub to: String,
+    pub kind: GraphEdgeKind,
+    pub variables: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PackageGraph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+/// Names added, removed or changed between two reads of a watched file set.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChangeSet {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<String>,
+}
+
+impl ChangeSet {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
     }
 }
 
@@ -107,6 +410,29 @@ pub struct PresetData {
     pub houdini: String,
     pub avatar: String,
     pub avatar_path: String,
+    #[serde(default)]
+    pub executable: String,
+    /// Package names, highest priority first; applied to the load order at launch.
+    #[serde(default)]
+    pub priority: Vec<String>,
+}
+
+/// What a launch is made of, shared by the launch itself, its preview and an
+/// exported launch script.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LaunchRequest {
+    /// Not needed for a preview.
+    #[serde(default)]
+    pub exe_path: String,
+    pub config_root: String,
+    #[serde(default)]
+    pub env_vars: Vec<(String, String)>,
+    /// Packages to load; the user's enabled packages when absent.
+    #[serde(default)]
+    pub packages: Option<Vec<String>>,
+    /// Preset whose `priority` orders the packages.
+    #[serde(default)]
+    pub preset: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -115,6 +441,29 @@ pub struct PresetsFile {
     pub presets: Vec<PresetData>,
 }
 
+/// A directory packages are read from. Subfolders are scanned up to
+/// `max_depth` levels deep; 0 reads only the directory itself.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageSource {
+    pub name: String,
+    pub path: PathBuf,
+    #[serde(default)]
+    pub max_depth: usize,
+    #[serde(default)]
+    pub priority: i32,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PackageSourcesFile {
+    pub sources: Vec<PackageSource>,
+}
+
+/// Per-user enabled state of packages, kept outside the shared package files.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PackageStatesFile {
+    pub enabled: std::collections::BTreeMap<String, bool>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FavoritesFile {
     pub favorites: Vec<String>,
@@ -126,9 +475,112 @@ pub struct GlobalVarsFile {
     pub vars: std::collections::HashMap<String, String>,
 }
 
+/// Executables a Houdini `bin` folder may ship, without the platform suffix.
+pub const HOUDINI_EXECUTABLES: &[&str] = &[
+    "houdini",
+    "houdinifx",
+    "houdinicore",
+    "happrentice",
+    "hython",
+    "hbatch",
+    "hserver",
+    "mplay",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HoudiniExecutable {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HoudiniBuildType {
+    Production,
+    Daily,
+    Unknown,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HoudiniVersion {
     pub name: String,
     pub path: PathBuf,
     pub bin_path: PathBuf,
+    pub root: PathBuf,
+    pub major: u32,
+    pub minor: u32,
+    pub build: u32,
+    pub executables: Vec<HoudiniExecutable>,
+    pub python_version: Option<String>,
+    pub build_type: HoudiniBuildType,
+    pub is_alias: bool,
+}
+
+impl HoudiniVersion {
+    /// Parses the first dotted number in `s`, e.g. "Houdini 20.5.332" or "hfs20.5".
+    /// Missing components are returned as 0.
+    pub fn parse_version(s: &str) -> Option<(u32, u32, u32)> {
+        let start = s.find(|c: char| c.is_ascii_digit())?;
+        let digits: String = s[start..]
+            .chars()
+            .take_while(|c| c.is_ascii_digit() || *c == '.')
+            .collect();
+        let mut parts = digits.split('.').filter(|p| !p.is_empty()).map(|p| p.parse::<u32>());
+        let major = parts.next()?.ok()?;
+        let minor = parts.next().and_then(|p| p.ok()).unwrap_or(0);
+        let build = parts.next().and_then(|p| p.ok()).unwrap_or(0);
+        Some((major, minor, build))
+    }
+
+    /// Looks up an executable by name; "houdinifx", "houdinifx.exe" and
+    /// "HoudiniFX" all refer to the same binary.
+    pub fn find_executable(&self, name: &str) -> Option<&HoudiniExecutable> {
+        let name = name.trim();
+        let name = name.strip_suffix(".exe").unwrap_or(name);
+        self.executables.iter().find(|e| e.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn version_tuple(&self) -> (u32, u32, u32) {
+        (self.major, self.minor, self.build)
+    }
+
+    /// Matches a version spec such as "20", "20.5", "20.5.332", "20.x" or "latest 20.x".
+    /// A bare "latest" matches every version.
+    pub fn matches_spec(&self, spec: &str) -> bool {
+        let spec = spec.trim();
+        let spec = spec.strip_prefix("latest").unwrap_or(spec).trim();
+        if spec.is_empty() {
+            return true;
+        }
+
+        let wanted: Vec<&str> = spec.split('.').filter(|p| !p.is_empty()).collect();
+        let actual = [self.major, self.minor, self.build];
+        if wanted.is_empty() || wanted.len() > actual.len() {
+            return false;
+        }
+        wanted.iter().zip(actual.iter()).all(|(w, a)| {
+            *w == "x" || *w == "*" || w.parse::<u32>().map(|n| n == *a).unwrap_or(false)
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HoudiniRootsFile {
+    pub roots: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HoudiniRootStatus {
+    Reachable,
+    Empty,
+    Missing,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HoudiniRoot {
+    pub path: PathBuf,
+    pub status: HoudiniRootStatus,
+    pub version_count: usize,
+    pub user_defined: bool,
 }
@@ -0,0 +1,204 @@
+use crate::utils::expression::{evaluate, ExprContext};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// How an `env` entry combines with an existing value of the same variable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EnvMethod {
+    Prepend,
+    Append,
+    #[default]
+    Replace,
+}
+
+impl EnvMethod {
+    pub fn parse(s: &str) -> Self {
+        match s.trim().to_lowercase().as_str() {
+            "prepend" => EnvMethod::Prepend,
+            "append" => EnvMethod::Append,
+            _ => EnvMethod::Replace,
+        }
+    }
+}
+
+/// A single value, optionally guarded by an expression such as
+/// `houdini_os == 'linux'`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EnvValue {
+    pub value: String,
+    pub condition: Option<String>,
+}
+
+impl EnvValue {
+    /// Unconditional values always apply; conditional ones only when a build
+    /// is given and the condition holds for it.
+    pub fn applies(&self, ctx: Option<&ExprContext>) -> bool {
+        match (&self.condition, ctx) {
+            (None, _) => true,
+            (Some(cond), Some(ctx)) => evaluate(cond, ctx).unwrap_or(false),
+            (Some(_), None) => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvEntry {
+    pub key: String,
+    pub values: Vec<EnvValue>,
+    pub method: EnvMethod,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "value", rename_all = "lowercase")]
+pub enum PackageEnable {
+    Bool(bool),
+    Expr(String),
+}
+
+impl Default for PackageEnable {
+    fn default() -> Self {
+        PackageEnable::Bool(true)
+    }
+}
+
+/// Typed view of a SideFX package file. Unknown keys are ignored here and
+/// remain available on `Package::data`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PackageSpec {
+    pub enable: PackageEnable,
+    pub env: Vec<EnvEntry>,
+    pub hpath: Vec<EnvValue>,
+    pub package_path: Vec<EnvValue>,
+    pub load_package_once: bool,
+    pub recursive_path: bool,
+    pub requires: Vec<String>,
+}
+
+impl PackageSpec {
+    pub fn from_value(data: &Value) -> Self {
+        let enable = match data.get("enable") {
+            Some(Value::Bool(b)) => PackageEnable::Bool(*b),
+            Some(Value::String(s)) => match s.trim() {
+                "true" | "True" => PackageEnable::Bool(true),
+                "false" | "False" => PackageEnable::Bool(false),
+                expr => PackageEnable::Expr(expr.to_string()),
+            },
+            _ => PackageEnable::default(),
+        };
+
+        let mut env = Vec::new();
+        if let Some(entries) = data.get("env").and_then(|v| v.as_array()) {
+            for entry in entries {
+                parse_env_entry(entry, &mut env);
+            }
+        }
+
+        // `path` is the older spelling of `hpath`; Houdini honours both.
+        let mut hpath = Vec::new();
+        for key in ["hpath", "path"] {
+            if let Some(v) = data.get(key) {
+                hpath.extend(parse_values(v, None));
+            }
+        }
+
+        let package_path = data.get("package_path")
+            .map(|v| parse_values(v, None))
+            .unwrap_or_default();
+
+        let requires = match data.get("requires") {
+            Some(Value::String(s)) => vec![s.clone()],
+            Some(Value::Array(arr)) => arr.iter()
+                .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        Self {
+            enable,
+            env,
+            hpath,
+            package_path,
+            load_package_once: as_flag(data.get("load_package_once")),
+            recursive_path: as_flag(data.get("recursive_path")),
+            requires,
+        }
+    }
+}
+
+fn as_flag(value: Option<&Value>) -> bool {
+    match value {
+        Some(Value::Bool(b)) => *b,
+        Some(Value::String(s)) => s.eq_ignore_ascii_case("true"),
+        Some(Value::Number(n)) => n.as_i64().map(|n| n != 0).unwrap_or(false),
+        _ => false,
+    }
+}
+
+fn scalar_to_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// Flattens a value into its list of (possibly conditional) strings. Accepts a
+/// scalar, an array of values, or an object mapping conditions to values.
+fn parse_values(value: &Value, condition: Option<&str>) -> Vec<EnvValue> {
+    if let Some(s) = scalar_to_string(value) {
+        return vec![EnvValue {
+            value: s,
+            condition: condition.map(|c| c.to_string()),
+        }];
+    }
+
+    match value {
+        Value::Array(arr) => arr.iter()
+            .flat_map(|v| parse_values(v, condition))
+            .collect(),
+        Value::Object(obj) => obj.iter()
+            .flat_map(|(cond, v)| {
+                let cond = match condition {
+                    Some(outer) => format!("({}) and ({})", outer, cond),
+                    None => cond.clone(),
+                };
+                parse_values(v, Some(&cond))
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Parses one element of the `env` array. Houdini accepts `{"VAR": value}`,
+/// `{"VAR": {"value": ..., "method": ...}}` and `{"var": "VAR", "value": ...}`.
+fn parse_env_entry(entry: &Value, out: &mut Vec<EnvEntry>) {
+    let Some(obj) = entry.as_object() else {
+        return;
+    };
+
+    if let (Some(key), Some(value)) = (obj.get("var").and_then(|v| v.as_str()), obj.get("value")) {
+        out.push(EnvEntry {
+            key: key.to_string(),
+            values: parse_values(value, None),
+            method: obj.get("method").and_then(|m| m.as_str()).map(EnvMethod::parse).unwrap_or_default(),
+        });
+        return;
+    }
+
+    for (key, value) in obj {
+        let (values, method) = match value.as_object() {
+            Some(inner) if inner.contains_key("value") => (
+                parse_values(&inner["value"], None),
+                inner.get("method").and_then(|m| m.as_str()).map(EnvMethod::parse).unwrap_or_default(),
+            ),
+            _ => (parse_values(value, None), EnvMethod::default()),
+        };
+        out.push(EnvEntry {
+            key: key.clone(),
+            values,
+            method,
+        });
+    }
+}
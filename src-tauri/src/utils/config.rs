@@ -62,6 +62,22 @@ pub fn get_houdini_exe_txt_path() -> PathBuf {
     get_config_dir().join("houdini_exe.txt")
 }
 
+pub fn get_package_states_json_path() -> PathBuf {
+    get_config_dir().join("package_states.json")
+}
+
+pub fn get_package_sources_json_path() -> PathBuf {
+    get_config_dir().join("package_sources.json")
+}
+
+pub fn get_staging_root() -> PathBuf {
+    std::env::temp_dir().join(CONFIG_DIR_NAME).join("staged")
+}
+
+pub fn get_houdini_roots_json_path() -> PathBuf {
+    get_config_dir().join("houdini_roots.json")
+}
+
 pub fn looks_like_config_root(path: &Path) -> bool {
     path.join("packages").is_dir()
 }
@@ -97,6 +113,20 @@ pub fn load_json_file<T: serde::de::DeserializeOwned>(path: &Path) -> Option<T>
     }
 }
 
+/// Like `load_json_file`, but a file that exists and cannot be read or parsed
+/// is an error rather than `None`, so callers that write the file back do not
+/// overwrite a user's hand-edited copy.
+pub fn load_json_file_checked<T: serde::de::DeserializeOwned>(path: &Path) -> Result<Option<T>, String> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&content)
+        .map(Some)
+        .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
+
 pub fn save_json_file<T: serde::Serialize>(path: &Path, data: &T) -> Result<(), std::io::Error> {
     let content = serde_json::to_string_pretty(data).unwrap_or_default();
     std::fs::write(path, content)
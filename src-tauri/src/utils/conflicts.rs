@@ -0,0 +1,84 @@
+use crate::models::{EnvConflict, EnvContribution, EnvMethod, Package};
+use crate::utils::expand::{Expander, PATH_SEPARATOR};
+use crate::utils::expression::ExprContext;
+use std::collections::BTreeMap;
+
+/// Houdini reads package files alphabetically by file name.
+pub fn houdini_load_order<'a>(packages: &[&'a Package]) -> Vec<&'a Package> {
+    let mut ordered = packages.to_vec();
+    ordered.sort_by_key(|p| {
+        p.file_path.file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| p.name.clone())
+    });
+    ordered
+}
+
+/// Load order with a preset's priority list applied. `priority` names
+/// packages highest priority first; they are read after every unlisted
+/// package, the highest last, so their prepends and replaces win. Unlisted
+/// packages keep Houdini's alphabetical order.
+pub fn prioritized_load_order<'a>(packages: &[&'a Package], priority: &[String]) -> Vec<&'a Package> {
+    let rank = |p: &Package| priority.iter().position(|name| *name == p.name);
+    let mut ordered = houdini_load_order(packages);
+    // Stable sort: unlisted first, then listed from lowest to highest priority.
+    ordered.sort_by_key(|p| match rank(p) {
+        None => (0, 0),
+        Some(i) => (1, priority.len() - i),
+    });
+    ordered
+}
+
+/// Finds variables where a later package's `replace` discards what an earlier
+/// package set. `packages` should be the enabled set; the winner of each
+/// conflict is the last package to replace the variable in load order, with a
+/// preset's `priority` applied as the launcher stages them.
+pub fn find_env_conflicts(
+    packages: &[&Package],
+    priority: &[String],
+    expander: &Expander,
+    ctx: Option<&ExprContext>,
+) -> Vec<EnvConflict> {
+    let mut by_key: BTreeMap<String, Vec<EnvContribution>> = BTreeMap::new();
+
+    for package in prioritized_load_order(packages, priority) {
+        for entry in &package.spec.env {
+            let values: Vec<&str> = entry.values.iter()
+                .filter(|v| v.applies(ctx))
+                .map(|v| v.value.as_str())
+                .collect();
+            if values.is_empty() {
+                continue;
+            }
+            by_key.entry(entry.key.clone()).or_default().push(EnvContribution {
+                package: package.name.clone(),
+                value: values.join(PATH_SEPARATOR),
+                method: entry.method,
+            });
+        }
+    }
+
+    by_key.into_iter()
+        .filter_map(|(variable, contributions)| {
+            let last_replace = contributions.iter()
+                .rposition(|c| c.method == EnvMethod::Replace)?;
+            let winner = &contributions[last_replace].package;
+            let mut overwritten: Vec<String> = Vec::new();
+            for c in &contributions[..last_replace] {
+                if c.package != *winner && !overwritten.contains(&c.package) {
+                    overwritten.push(c.package.clone());
+                }
+            }
+            if overwritten.is_empty() {
+                return None;
+            }
+            Some(EnvConflict {
+                winner: contributions[last_replace].package.clone(),
+                final_value: expander.value(&variable).ok().flatten(),
+                variable,
+                overwritten,
+                contributions,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,129 @@
+use crate::models::{DependencyIssue, DependencyIssueKind, LoadPlan, Package};
+use crate::utils::expression::ExprContext;
+use std::collections::HashSet;
+
+fn find_package<'a>(packages: &'a [Package], name: &str) -> Option<&'a Package> {
+    packages.iter()
+        .find(|p| p.name == name)
+        .or_else(|| packages.iter().find(|p| p.name.eq_ignore_ascii_case(name)))
+}
+
+struct Resolver<'a> {
+    packages: &'a [Package],
+    ctx: Option<&'a ExprContext>,
+    order: Vec<String>,
+    done: HashSet<String>,
+    stack: Vec<String>,
+    issues: Vec<DependencyIssue>,
+}
+
+impl<'a> Resolver<'a> {
+    fn visit(&mut self, package: &'a Package) {
+        if self.done.contains(&package.name) {
+            return;
+        }
+        if let Some(start) = self.stack.iter().position(|n| n == &package.name) {
+            let mut chain = self.stack[start..].to_vec();
+            chain.push(package.name.clone());
+            self.issues.push(DependencyIssue {
+                package: self.stack.last().cloned().unwrap_or_default(),
+                dependency: package.name.clone(),
+                kind: DependencyIssueKind::Cycle,
+                detail: chain.join(" -> "),
+            });
+            return;
+        }
+
+        self.stack.push(package.name.clone());
+        for required in &package.spec.requires {
+            let required = required.trim();
+            match find_package(self.packages, required) {
+                None => self.issues.push(DependencyIssue {
+                    package: package.name.clone(),
+                    dependency: required.to_string(),
+                    kind: DependencyIssueKind::Missing,
+                    detail: format!("\"{}\" requires \"{}\", which is not installed", package.name, required),
+                }),
+                Some(dep) if !dep.enabled || !dep.spec_allows(self.ctx) => self.issues.push(DependencyIssue {
+                    package: package.name.clone(),
+                    dependency: dep.name.clone(),
+                    kind: DependencyIssueKind::Disabled,
+                    detail: format!("\"{}\" requires \"{}\", which is disabled", package.name, dep.name),
+                }),
+                Some(dep) => self.visit(dep),
+            }
+        }
+        self.stack.pop();
+
+        // A package is loaded once however many dependents require it.
+        self.done.insert(package.name.clone());
+        self.order.push(package.name.clone());
+    }
+}
+
+/// Works out the full set of packages loaded for `selected`, following
+/// `requires` transitively. Dependencies come before their dependents.
+pub fn resolve_dependencies(selected: &[String], packages: &[Package], ctx: Option<&ExprContext>) -> LoadPlan {
+    let mut resolver = Resolver {
+        packages,
+        ctx,
+        order: Vec::new(),
+        done: HashSet::new(),
+        stack: Vec::new(),
+        issues: Vec::new(),
+    };
+
+    let mut unknown = Vec::new();
+    for name in selected {
+        match find_package(packages, name) {
+            Some(pkg) => resolver.visit(pkg),
+            None => unknown.push(name.clone()),
+        }
+    }
+
+    let selected_set: HashSet<&str> = selected.iter().map(|s| s.as_str()).collect();
+    let pulled_in = resolver.order.iter()
+        .filter(|n| !selected_set.contains(n.as_str()))
+        .cloned()
+        .collect();
+
+    let mut issues = resolver.issues;
+
+    // The launcher stages one file per package name. Houdini, reading the
+    // source directories itself, only does the same for packages that set
+    // `load_package_once`.
+    for name in &resolver.order {
+        let Some(pkg) = find_package(packages, name) else {
+            continue;
+        };
+        if !pkg.spec.load_package_once && !pkg.shadows.is_empty() {
+            let others: Vec<String> = pkg.shadows.iter().map(|p| p.display().to_string()).collect();
+            issues.push(DependencyIssue {
+                package: pkg.name.clone(),
+                dependency: pkg.name.clone(),
+                kind: DependencyIssueKind::Duplicate,
+                detail: format!(
+                    "\"{}\" does not set load_package_once; Houdini would also load {}, the launcher only loads {}",
+                    pkg.name,
+                    others.join(", "),
+                    pkg.file_path.display()
+                ),
+            });
+        }
+    }
+
+    for name in unknown {
+        issues.push(DependencyIssue {
+            package: name.clone(),
+            dependency: name.clone(),
+            kind: DependencyIssueKind::Missing,
+            detail: format!("\"{}\" is not installed", name),
+        });
+    }
+
+    LoadPlan {
+        packages: resolver.order,
+        pulled_in,
+        issues,
+    }
+}
@@ -0,0 +1,383 @@
+use crate::models::{EnvMethod, HoudiniVersion, Package};
+use crate::utils::expression::ExprContext;
+use std::collections::HashMap;
+use std::path::Path;
+
+pub const PATH_SEPARATOR: &str = if cfg!(target_os = "windows") { ";" } else { ":" };
+
+/// Splits a search-path value into its entries. Houdini accepts ";" on every
+/// platform, and ":" as well outside Windows.
+pub fn split_search_path(value: &str) -> impl Iterator<Item = &str> {
+    value.split(|c: char| c == ';' || (c == ':' && !cfg!(target_os = "windows")))
+        .map(|part| part.trim())
+        .filter(|part| !part.is_empty())
+}
+
+/// Reads the variable name right after a `$`, as `NAME` or `{NAME}`. Returns
+/// the name and how many bytes it takes up; the name is empty when there is none.
+fn parse_reference(after: &str) -> (&str, usize) {
+    if let Some(braced) = after.strip_prefix('{') {
+        match braced.find('}') {
+            Some(end) => (&braced[..end], end + 2),
+            None => ("", 0),
+        }
+    } else {
+        let end = after
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(after.len());
+        (&after[..end], end)
+    }
+}
+
+/// The names of the variables a value refers to, in order of appearance.
+pub fn referenced_vars(raw: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut rest = raw;
+    while let Some(pos) = rest.find('$') {
+        let after = &rest[pos + 1..];
+        let (name, consumed) = parse_reference(after);
+        if !name.is_empty() {
+            names.push(name);
+        }
+        rest = &after[consumed..];
+    }
+    names
+}
+
+/// Replaces every `$NAME` and `${NAME}` reference to `name` with `value`,
+/// leaving longer names that only start with it alone.
+pub fn replace_var(raw: &str, name: &str, value: &str) -> String {
+    let mut out = String::with_capacity(raw.len());
+    let mut rest = raw;
+    while let Some(pos) = rest.find('$') {
+        out.push_str(&rest[..pos]);
+        let after = &rest[pos + 1..];
+        let (found, consumed) = parse_reference(after);
+        if consumed > 0 && found == name {
+            out.push_str(value);
+        } else {
+            out.push('$');
+            out.push_str(&after[..consumed]);
+        }
+        rest = &after[consumed..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// One variable definition, in the order Houdini would apply it.
+#[derive(Debug, Clone)]
+pub struct VarDef {
+    pub key: String,
+    pub value: String,
+    pub method: EnvMethod,
+    pub source: String,
+    /// Set in the process environment as is; `$` references are not expanded.
+    pub literal: bool,
+}
+
+/// Expands `$VAR` and `${VAR}` references against an ordered list of
+/// definitions, then Houdini's built-in variables, then the process
+/// environment. Unknown variables are left untouched, as Houdini does.
+///
+/// A definition sees the latest earlier definition of each variable. A
+/// reference to a variable that is only defined later (typically by another
+/// package) resolves to that later definition; cycles are reported as errors.
+#[derive(Debug, Clone)]
+pub struct Expander {
+    defs: Vec<VarDef>,
+    builtins: HashMap<String, String>,
+    process_env: bool,
+}
+
+impl Expander {
+    pub fn new() -> Self {
+        Self {
+            defs: Vec::new(),
+            builtins: HashMap::new(),
+            process_env: true,
+        }
+    }
+
+    /// An expander seeded with the variables Houdini defines itself for the
+    /// given build, plus `$CONFIG_ROOT_PATH`.
+    pub fn for_houdini(version: Option<&HoudiniVersion>, config_root: &Path) -> Self {
+        let mut expander = Self::new();
+        expander.builtin("CONFIG_ROOT_PATH", config_root.to_string_lossy());
+
+        // Houdini points $HOME at the Documents folder on Windows.
+        let home = if cfg!(target_os = "windows") {
+            dirs::document_dir().or_else(dirs::home_dir)
+        } else {
+            dirs::home_dir()
+        };
+        if let Some(home) = home {
+            expander.builtin("HOME", home.to_string_lossy());
+        }
+
+        if let Some(version) = version {
+            let resources = version.bin_path.parent().unwrap_or(&version.path);
+            expander.builtin("HFS", resources.to_string_lossy());
+            expander.builtin("HH", resources.join("houdini").to_string_lossy());
+            expander.builtin("HOUDINI_VERSION", format!("{}.{}.{}", version.major, version.minor, version.build));
+            expander.builtin("HOUDINI_MAJOR_RELEASE", version.major.to_string());
+            expander.builtin("HOUDINI_MINOR_RELEASE", version.minor.to_string());
+            expander.builtin("HOUDINI_BUILD_VERSION", version.build.to_string());
+        }
+        expander
+    }
+
+    pub fn builtin(&mut self, key: &str, value: impl Into<String>) {
+        self.builtins.insert(key.to_string(), value.into());
+    }
+
+    pub fn define(&mut self, key: &str, value: &str, method: EnvMethod, source: &str) {
+        self.defs.push(VarDef {
+            key: key.to_string(),
+            value: value.to_string(),
+            method,
+            source: source.to_string(),
+            literal: false,
+        });
+    }
+
+    /// Defines a variable the way a process environment does: its value is
+    /// taken as is, without expanding references.
+    pub fn define_literal(&mut self, key: &str, value: &str, source: &str) {
+        self.defs.push(VarDef {
+            key: key.to_string(),
+            value: value.to_string(),
+            method: EnvMethod::Replace,
+            source: source.to_string(),
+            literal: true,
+        });
+    }
+
+    /// Adds every `env` entry of a package, then its `hpath` as a prepend to
+    /// `HOUDINI_PATH`. Conditional values are kept only when `ctx` is given and
+    /// their condition holds; multiple values are joined with the platform path
+    /// separator.
+    pub fn define_package(&mut self, package: &Package, ctx: Option<&ExprContext>) {
+        for entry in &package.spec.env {
+            let values: Vec<&str> = entry.values.iter()
+                .filter(|v| v.applies(ctx))
+                .map(|v| v.value.as_str())
+                .collect();
+            if values.is_empty() {
+                continue;
+            }
+            self.define(&entry.key, &values.join(PATH_SEPARATOR), entry.method, &package.name);
+        }
+
+        let hpath: Vec<&str> = package.spec.hpath.iter()
+            .filter(|v| v.applies(ctx))
+            .map(|v| v.value.as_str())
+            .collect();
+        if !hpath.is_empty() {
+            self.define("HOUDINI_PATH", &hpath.join(PATH_SEPARATOR), EnvMethod::Prepend, &package.name);
+        }
+    }
+
+    pub fn defs(&self) -> &[VarDef] {
+        &self.defs
+    }
+
+    /// Expands `raw` as if it appeared after every definition.
+    pub fn expand(&self, raw: &str) -> Result<String, String> {
+        self.expand_at(raw, self.defs.len(), None, &mut Vec::new())
+    }
+
+    /// The final value of `key` once every definition has been applied.
+    pub fn value(&self, key: &str) -> Result<Option<String>, String> {
+        self.value_before(key, self.defs.len(), &mut Vec::new())
+    }
+
+    fn base_value(&self, key: &str) -> Option<String> {
+        if let Some(v) = self.builtins.get(key) {
+            return Some(v.clone());
+        }
+        if self.process_env {
+            return std::env::var(key).ok();
+        }
+        None
+    }
+
+    fn value_before(&self, key: &str, upto: usize, stack: &mut Vec<usize>) -> Result<Option<String>, String> {
+        match self.defs[..upto].iter().rposition(|d| d.key == key) {
+            Some(idx) => self.eval_def(idx, stack).map(Some),
+            None => Ok(self.base_value(key)),
+        }
+    }
+
+    fn eval_def(&self, idx: usize, stack: &mut Vec<usize>) -> Result<String, String> {
+        if let Some(start) = stack.iter().position(|&i| i == idx) {
+            let chain: Vec<&str> = stack[start..].iter()
+                .chain(std::iter::once(&idx))
+                .map(|&i| self.defs[i].key.as_str())
+                .collect();
+            return Err(format!("Variable cycle: {}", chain.join(" -> ")));
+        }
+
+        let def = &self.defs[idx];
+        if def.literal {
+            return Ok(def.value.clone());
+        }
+        stack.push(idx);
+        let expanded = self.expand_at(&def.value, idx, Some(&def.key), stack)?;
+        let result = match def.method {
+            EnvMethod::Replace => expanded,
+            EnvMethod::Prepend | EnvMethod::Append => match self.value_before(&def.key, idx, stack)? {
+                Some(prev) if !prev.is_empty() => {
+                    if def.method == EnvMethod::Prepend {
+                        format!("{}{}{}", expanded, PATH_SEPARATOR, prev)
+                    } else {
+                        format!("{}{}{}", prev, PATH_SEPARATOR, expanded)
+                    }
+                }
+                _ => expanded,
+            },
+        };
+        stack.pop();
+        Ok(result)
+    }
+
+    fn lookup(&self, name: &str, at: usize, own_key: Option<&str>, stack: &mut Vec<usize>) -> Result<Option<String>, String> {
+        // `PATH = "$PATH:..."` refers to the value before this definition.
+        if own_key == Some(name) {
+            return self.value_before(name, at, stack);
+        }
+        if let Some(idx) = self.defs[..at].iter().rposition(|d| d.key == name) {
+            return self.eval_def(idx, stack).map(Some);
+        }
+        if let Some(offset) = self.defs[at..].iter().position(|d| d.key == name) {
+            return self.eval_def(at + offset, stack).map(Some);
+        }
+        Ok(self.base_value(name))
+    }
+
+    fn expand_at(&self, raw: &str, at: usize, own_key: Option<&str>, stack: &mut Vec<usize>) -> Result<String, String> {
+        let mut out = String::with_capacity(raw.len());
+        let mut rest = raw;
+
+        while let Some(pos) = rest.find('$') {
+            out.push_str(&rest[..pos]);
+            let after = &rest[pos + 1..];
+
+            let (name, consumed) = parse_reference(after);
+
+            if name.is_empty() {
+                out.push('$');
+                rest = after;
+                continue;
+            }
+
+            match self.lookup(name, at, own_key, stack)? {
+                Some(value) => out.push_str(&value),
+                None => out.push_str(&rest[pos..pos + 1 + consumed]),
+            }
+            rest = &after[consumed..];
+        }
+
+        out.push_str(rest);
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An expander that ignores the process environment, so results do not
+    /// depend on the machine running the tests.
+    fn isolated() -> Expander {
+        let mut expander = Expander::new();
+        expander.process_env = false;
+        expander
+    }
+
+    #[test]
+    fn longer_names_are_not_matched_by_a_prefix() {
+        let mut e = isolated();
+        e.builtin("HOUDINI", "wrong");
+        e.builtin("HOUDINI_VERSION", "20.5.332");
+        assert_eq!(e.expand("$HOUDINI_VERSION/x").unwrap(), "20.5.332/x");
+        assert_eq!(e.expand("${HOUDINI}_VERSION").unwrap(), "wrong_VERSION");
+    }
+
+    #[test]
+    fn literal_definitions_are_not_expanded() {
+        let mut e = isolated();
+        e.define("ROOT", "/studio", EnvMethod::Replace, "a");
+        e.define_literal("RAW", "$ROOT/otls", "launcher");
+        e.define("USES", "$RAW", EnvMethod::Replace, "b");
+        assert_eq!(e.value("RAW").unwrap().as_deref(), Some("$ROOT/otls"));
+        assert_eq!(e.value("USES").unwrap().as_deref(), Some("$ROOT/otls"));
+    }
+
+    #[test]
+    fn replace_var_matches_whole_names() {
+        let raw = "$HOUDINI_PACKAGE_PATH/otls;${HOUDINI_PACKAGE_PATH}/x;$HOUDINI_PACKAGE_PATH_EXTRA;$";
+        assert_eq!(
+            replace_var(raw, "HOUDINI_PACKAGE_PATH", "/pkg"),
+            "/pkg/otls;/pkg/x;$HOUDINI_PACKAGE_PATH_EXTRA;$"
+        );
+    }
+
+    #[test]
+    fn braced_and_unknown_references() {
+        let mut e = isolated();
+        e.define("ROOT", "/studio", EnvMethod::Replace, "a");
+        assert_eq!(e.expand("${ROOT}/otls").unwrap(), "/studio/otls");
+        assert_eq!(e.expand("$MISSING/otls").unwrap(), "$MISSING/otls");
+        assert_eq!(e.expand("cost: $5 and $").unwrap(), "cost: $5 and $");
+    }
+
+    #[test]
+    fn forward_reference_resolves_to_later_definition() {
+        let mut e = isolated();
+        e.define("TOOL_HDA", "$TOOL_ROOT/hda", EnvMethod::Replace, "a");
+        e.define("TOOL_ROOT", "/tools/x", EnvMethod::Replace, "b");
+        assert_eq!(e.value("TOOL_HDA").unwrap().as_deref(), Some("/tools/x/hda"));
+    }
+
+    #[test]
+    fn later_definition_sees_latest_earlier_one() {
+        let mut e = isolated();
+        e.define("ROOT", "/old", EnvMethod::Replace, "a");
+        e.define("HDA", "$ROOT/hda", EnvMethod::Replace, "b");
+        e.define("ROOT", "/new", EnvMethod::Replace, "c");
+        assert_eq!(e.value("HDA").unwrap().as_deref(), Some("/old/hda"));
+        assert_eq!(e.value("ROOT").unwrap().as_deref(), Some("/new"));
+    }
+
+    #[test]
+    fn self_reference_uses_the_previous_value() {
+        let mut e = isolated();
+        e.builtin("PATH", "/usr/bin");
+        e.define("PATH", &format!("$PATH{}/tools/bin", PATH_SEPARATOR), EnvMethod::Replace, "a");
+        assert_eq!(
+            e.value("PATH").unwrap(),
+            Some(format!("/usr/bin{}/tools/bin", PATH_SEPARATOR))
+        );
+    }
+
+    #[test]
+    fn prepend_and_append_join_with_the_previous_value() {
+        let mut e = isolated();
+        e.define("PYTHONPATH", "/a", EnvMethod::Replace, "a");
+        e.define("PYTHONPATH", "/b", EnvMethod::Prepend, "b");
+        e.define("PYTHONPATH", "/c", EnvMethod::Append, "c");
+        let sep = PATH_SEPARATOR;
+        assert_eq!(e.value("PYTHONPATH").unwrap(), Some(format!("/b{sep}/a{sep}/c")));
+    }
+
+    #[test]
+    fn cycles_are_reported() {
+        let mut e = isolated();
+        e.define("A", "$B/x", EnvMethod::Replace, "a");
+        e.define("B", "$A/y", EnvMethod::Replace, "b");
+        let err = e.value("A").unwrap_err();
+        assert!(err.starts_with("Variable cycle"), "{}", err);
+        assert!(e.expand("$B").is_err());
+    }
+}
@@ -0,0 +1,336 @@
+use crate::models::HoudiniVersion;
+use std::cmp::Ordering;
+
+/// Variables available to package `enable` expressions and conditional values.
+#[derive(Debug, Clone)]
+pub struct ExprContext {
+    pub houdini_version: String,
+    pub houdini_os: String,
+    pub houdini_python: String,
+}
+
+impl ExprContext {
+    pub fn for_version(version: &HoudiniVersion) -> Self {
+        Self {
+            houdini_version: format!("{}.{}.{}", version.major, version.minor, version.build),
+            houdini_os: houdini_os().to_string(),
+            houdini_python: version.python_version
+                .as_ref()
+                .map(|v| format!("python{}", v))
+                .unwrap_or_default(),
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Option<&str> {
+        match name {
+            "houdini_version" => Some(&self.houdini_version),
+            "houdini_os" => Some(&self.houdini_os),
+            "houdini_python" => Some(&self.houdini_python),
+            _ => None,
+        }
+    }
+}
+
+/// The `houdini_os` value Houdini uses for the current platform.
+pub fn houdini_os() -> &'static str {
+    match std::env::consts::OS {
+        "windows" => "windows",
+        "macos" => "macos",
+        _ => "linux",
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Op(&'static str),
+    LParen,
+    RParen,
+}
+
+fn tokenize(expr: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = expr.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '(' {
+            tokens.push(Token::LParen);
+            i += 1;
+        } else if c == ')' {
+            tokens.push(Token::RParen);
+            i += 1;
+        } else if c == '\'' || c == '"' {
+            let start = i + 1;
+            let end = chars[start..].iter()
+                .position(|&ch| ch == c)
+                .map(|p| start + p)
+                .ok_or_else(|| format!("Unterminated string in '{}'", expr))?;
+            tokens.push(Token::Str(chars[start..end].iter().collect()));
+            i = end + 1;
+        } else if c.is_alphanumeric() || c == '_' || c == '.' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '.') {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            match word.as_str() {
+                "and" => tokens.push(Token::Op("and")),
+                "or" => tokens.push(Token::Op("or")),
+                "not" => tokens.push(Token::Op("not")),
+                _ => tokens.push(Token::Ident(word)),
+            }
+        } else {
+            let two: String = chars[i..(i + 2).min(chars.len())].iter().collect();
+            let op = match two.as_str() {
+                "==" => "==",
+                "!=" => "!=",
+                "<=" => "<=",
+                ">=" => ">=",
+                "&&" => "and",
+                "||" => "or",
+                _ => match c {
+                    '<' => "<",
+                    '>' => ">",
+                    '!' => "not",
+                    _ => return Err(format!("Unexpected character '{}' in '{}'", c, expr)),
+                },
+            };
+            i += if matches!(two.as_str(), "==" | "!=" | "<=" | ">=" | "&&" | "||") { 2 } else { 1 };
+            tokens.push(Token::Op(op));
+        }
+    }
+
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone)]
+enum Val {
+    Bool(bool),
+    Str(String),
+}
+
+impl Val {
+    fn truthy(&self) -> bool {
+        match self {
+            Val::Bool(b) => *b,
+            Val::Str(s) => !s.is_empty() && s != "0" && !s.eq_ignore_ascii_case("false"),
+        }
+    }
+
+    fn as_text(&self) -> String {
+        match self {
+            Val::Bool(b) => b.to_string(),
+            Val::Str(s) => s.clone(),
+        }
+    }
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    pos: usize,
+    ctx: &'a ExprContext,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn parse_or(&mut self) -> Result<Val, String> {
+        let mut left = self.parse_and()?;
+        while self.peek() == Some(&Token::Op("or")) {
+            self.next();
+            let right = self.parse_and()?;
+            left = Val::Bool(left.truthy() || right.truthy());
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Val, String> {
+        let mut left = self.parse_not()?;
+        while self.peek() == Some(&Token::Op("and")) {
+            self.next();
+            let right = self.parse_not()?;
+            left = Val::Bool(left.truthy() && right.truthy());
+        }
+        Ok(left)
+    }
+
+    fn parse_not(&mut self) -> Result<Val, String> {
+        if self.peek() == Some(&Token::Op("not")) {
+            self.next();
+            let inner = self.parse_not()?;
+            return Ok(Val::Bool(!inner.truthy()));
+        }
+        self.parse_comparison()
+    }
+
+    fn parse_comparison(&mut self) -> Result<Val, String> {
+        let left = self.parse_primary()?;
+        let op = match self.peek() {
+            Some(Token::Op(op)) if matches!(*op, "==" | "!=" | "<" | ">" | "<=" | ">=") => *op,
+            _ => return Ok(left),
+        };
+        self.next();
+        let right = self.parse_primary()?;
+
+        let ord = compare_values(&left.as_text(), &right.as_text());
+        let result = match op {
+            "==" => ord == Ordering::Equal,
+            "!=" => ord != Ordering::Equal,
+            "<" => ord == Ordering::Less,
+            ">" => ord == Ordering::Greater,
+            "<=" => ord != Ordering::Greater,
+            _ => ord != Ordering::Less,
+        };
+        Ok(Val::Bool(result))
+    }
+
+    fn parse_primary(&mut self) -> Result<Val, String> {
+        match self.next() {
+            Some(Token::LParen) => {
+                let inner = self.parse_or()?;
+                match self.next() {
+                    Some(Token::RParen) => Ok(inner),
+                    _ => Err("Missing closing parenthesis".to_string()),
+                }
+            }
+            Some(Token::Str(s)) => Ok(Val::Str(s)),
+            Some(Token::Ident(name)) => match name.as_str() {
+                "true" | "True" => Ok(Val::Bool(true)),
+                "false" | "False" => Ok(Val::Bool(false)),
+                _ if name.starts_with(|c: char| c.is_ascii_digit()) => Ok(Val::Str(name)),
+                _ => self.ctx.lookup(&name)
+                    .map(|v| Val::Str(v.to_string()))
+                    .ok_or_else(|| format!("Unknown variable '{}'", name)),
+            },
+            Some(token) => Err(format!("Unexpected token {:?}", token)),
+            None => Err("Unexpected end of expression".to_string()),
+        }
+    }
+}
+
+fn version_parts(s: &str) -> Option<Vec<u32>> {
+    let parts: Vec<u32> = s.split('.')
+        .map(|p| p.parse::<u32>())
+        .collect::<Result<_, _>>()
+        .ok()?;
+    if parts.is_empty() { None } else { Some(parts) }
+}
+
+/// Compares two operands the way Houdini does: dotted versions are compared
+/// numerically up to the shorter of the two, so `'20.5.332' == '20.5'` holds.
+/// A shared alphabetic prefix such as "python" is ignored for the comparison.
+fn compare_values(left: &str, right: &str) -> Ordering {
+    let prefix_len = left.chars()
+        .zip(right.chars())
+        .take_while(|(a, b)| a == b && a.is_ascii_alphabetic())
+        .count();
+    let (l, r) = (&left[prefix_len..], &right[prefix_len..]);
+
+    match (version_parts(l), version_parts(r)) {
+        (Some(lv), Some(rv)) => {
+            let n = lv.len().min(rv.len());
+            lv[..n].cmp(&rv[..n])
+        }
+        _ => left.cmp(right),
+    }
+}
+
+/// Evaluates a package expression such as
+/// `houdini_version >= '20.0' and houdini_os == 'linux'`.
+pub fn evaluate(expr: &str, ctx: &ExprContext) -> Result<bool, String> {
+    let tokens = tokenize(expr)?;
+    if tokens.is_empty() {
+        return Err("Empty expression".to_string());
+    }
+
+    let mut parser = Parser { tokens, pos: 0, ctx };
+    let value = parser.parse_or()?;
+    if let Some(token) = parser.peek() {
+        return Err(format!("Unexpected token {:?} in '{}'", token, expr));
+    }
+    Ok(value.truthy())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ctx() -> ExprContext {
+        ExprContext {
+            houdini_version: "20.5.332".to_string(),
+            houdini_os: "linux".to_string(),
+            houdini_python: "python3.10".to_string(),
+        }
+    }
+
+    fn eval(expr: &str) -> Result<bool, String> {
+        evaluate(expr, &ctx())
+    }
+
+    #[test]
+    fn boolean_operators() {
+        assert_eq!(eval("houdini_os == 'linux' and houdini_version >= '20.0'"), Ok(true));
+        assert_eq!(eval("houdini_os == 'windows' and houdini_version >= '20.0'"), Ok(false));
+        assert_eq!(eval("houdini_os == 'windows' or houdini_os == 'linux'"), Ok(true));
+        assert_eq!(eval("not houdini_os == 'windows'"), Ok(true));
+        assert_eq!(eval("houdini_os == 'linux' && !(houdini_os == 'macos')"), Ok(true));
+    }
+
+    #[test]
+    fn parentheses_override_precedence() {
+        // `and` binds tighter than `or`.
+        assert_eq!(eval("houdini_os == 'linux' or houdini_os == 'macos' and false"), Ok(true));
+        assert_eq!(eval("(houdini_os == 'linux' or houdini_os == 'macos') and false"), Ok(false));
+        assert!(eval("(houdini_os == 'linux'").is_err());
+    }
+
+    #[test]
+    fn python_version_ignores_shared_prefix() {
+        assert_eq!(eval("houdini_python == 'python3.10'"), Ok(true));
+        assert_eq!(eval("houdini_python == 'python3.11'"), Ok(false));
+        assert_eq!(eval("houdini_python < 'python3.11'"), Ok(true));
+        assert_eq!(eval("houdini_python == 'python3'"), Ok(true));
+    }
+
+    #[test]
+    fn versions_compare_up_to_shorter_operand() {
+        assert_eq!(eval("houdini_version == '20.5'"), Ok(true));
+        assert_eq!(eval("houdini_version == '20.5.332'"), Ok(true));
+        assert_eq!(eval("houdini_version == '20.0'"), Ok(false));
+        assert_eq!(eval("houdini_version > '20.0'"), Ok(true));
+        assert_eq!(eval("houdini_version < '21'"), Ok(true));
+        // Numeric, not lexical: 20.10 is newer than 20.9.
+        assert_eq!(evaluate("'20.10' > '20.9'", &ctx()), Ok(true));
+    }
+
+    #[test]
+    fn unknown_variable_is_an_error() {
+        let err = eval("houdini_build == '1'").unwrap_err();
+        assert!(err.contains("houdini_build"), "{}", err);
+    }
+
+    #[test]
+    fn unterminated_string_is_an_error() {
+        let err = eval("houdini_os == 'linux").unwrap_err();
+        assert!(err.contains("Unterminated string"), "{}", err);
+    }
+
+    #[test]
+    fn empty_and_trailing_tokens_are_errors() {
+        assert!(eval("").is_err());
+        assert!(eval("houdini_os == 'linux' 'extra'").is_err());
+    }
+}
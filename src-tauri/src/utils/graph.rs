@@ -0,0 +1,145 @@
+use crate::models::{GraphEdge, GraphEdgeKind, GraphNode, GraphNodeStatus, Package, PackageGraph};
+use crate::utils::conflicts::houdini_load_order;
+use crate::utils::expand::referenced_vars;
+use crate::utils::expression::ExprContext;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphFormat {
+    Dot,
+    Json,
+}
+
+/// Builds the relations between all packages: `requires` entries, variables
+/// set by more than one package, and references to variables another package
+/// sets. Packages that are required but not found get a `Missing` node.
+pub fn build_package_graph(packages: &[Package], ctx: Option<&ExprContext>) -> PackageGraph {
+    let mut nodes: BTreeMap<String, GraphNodeStatus> = packages.iter()
+        .map(|p| {
+            let status = if p.enabled && p.spec_allows(ctx) {
+                GraphNodeStatus::Enabled
+            } else {
+                GraphNodeStatus::Disabled
+            };
+            (p.name.clone(), status)
+        })
+        .collect();
+    let mut edges: BTreeMap<(String, String, GraphEdgeKind), BTreeSet<String>> = BTreeMap::new();
+
+    for pkg in packages {
+        for required in &pkg.spec.requires {
+            let required = required.trim();
+            let target = packages.iter()
+                .find(|p| p.name.eq_ignore_ascii_case(required))
+                .map(|p| p.name.clone())
+                .unwrap_or_else(|| {
+                    nodes.entry(required.to_string()).or_insert(GraphNodeStatus::Missing);
+                    required.to_string()
+                });
+            edges.entry((pkg.name.clone(), target, GraphEdgeKind::Requires)).or_default();
+        }
+    }
+
+    // Link each package that sets a variable to the previous one that did, in
+    // the order Houdini reads them.
+    let all: Vec<&Package> = packages.iter().collect();
+    let mut setters: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for pkg in houdini_load_order(&all) {
+        for entry in &pkg.spec.env {
+            let list = setters.entry(entry.key.as_str()).or_default();
+            if !list.contains(&pkg.name.as_str()) {
+                list.push(&pkg.name);
+            }
+        }
+    }
+    for (var, list) in &setters {
+        for pair in list.windows(2) {
+            edges.entry((pair[0].to_string(), pair[1].to_string(), GraphEdgeKind::SharedVariable))
+                .or_default()
+                .insert(var.to_string());
+        }
+    }
+
+    for pkg in packages {
+        let values = pkg.spec.env.iter()
+            .flat_map(|e| e.values.iter())
+            .chain(&pkg.spec.hpath)
+            .chain(&pkg.spec.package_path);
+        for value in values {
+            for var in referenced_vars(&value.value) {
+                let Some(list) = setters.get(var) else {
+                    continue;
+                };
+                if list.contains(&pkg.name.as_str()) {
+                    continue;
+                }
+                for setter in list {
+                    edges.entry((pkg.name.clone(), setter.to_string(), GraphEdgeKind::VariableReference))
+                        .or_default()
+                        .insert(var.to_string());
+                }
+            }
+        }
+    }
+
+    PackageGraph {
+        nodes: nodes.into_iter()
+            .map(|(id, status)| GraphNode { id, status })
+            .collect(),
+        edges: edges.into_iter()
+            .map(|((from, to, kind), variables)| GraphEdge {
+                from,
+                to,
+                kind,
+                variables: variables.into_iter().collect(),
+            })
+            .collect(),
+    }
+}
+
+fn dot_quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Renders the graph for Graphviz. Disabled packages are grey and dashed,
+/// missing ones red; shared variables are dashed edges and variable
+/// references dotted.
+pub fn render_dot(graph: &PackageGraph) -> String {
+    let mut out = String::from("digraph packages {\n");
+    out.push_str("    rankdir=LR;\n");
+    out.push_str("    node [shape=box, style=\"rounded,filled\", fillcolor=\"#ffffff\", fontname=\"Helvetica\"];\n");
+    out.push_str("    edge [fontname=\"Helvetica\", fontsize=10];\n\n");
+
+    for node in &graph.nodes {
+        let attrs = match node.status {
+            GraphNodeStatus::Enabled => String::new(),
+            GraphNodeStatus::Disabled => {
+                " [style=\"rounded,filled,dashed\", fillcolor=\"#eeeeee\", fontcolor=\"#888888\"]".to_string()
+            }
+            GraphNodeStatus::Missing => format!(
+                " [style=\"rounded,dashed\", color=\"#cc0000\", fontcolor=\"#cc0000\", label={}]",
+                dot_quote(&format!("{} (missing)", node.id))
+            ),
+        };
+        out.push_str(&format!("    {}{};\n", dot_quote(&node.id), attrs));
+    }
+    out.push('\n');
+
+    for edge in &graph.edges {
+        let mut attrs = match edge.kind {
+            GraphEdgeKind::Requires => Vec::new(),
+            GraphEdgeKind::SharedVariable => vec!["style=dashed".to_string(), "color=\"#d08000\"".to_string()],
+            GraphEdgeKind::VariableReference => vec!["style=dotted".to_string(), "color=\"#2060c0\"".to_string()],
+        };
+        if !edge.variables.is_empty() {
+            attrs.push(format!("label={}", dot_quote(&edge.variables.join(", "))));
+        }
+        let attrs = if attrs.is_empty() { String::new() } else { format!(" [{}]", attrs.join(", ")) };
+        out.push_str(&format!("    {} -> {}{};\n", dot_quote(&edge.from), dot_quote(&edge.to), attrs));
+    }
+
+    out.push_str("}\n");
+    out
+}
@@ -0,0 +1,248 @@
+use crate::models::{ConfigPaths, PackageSpec};
+use crate::utils::expand::replace_var;
+use crate::utils::json_edit::parse_lenient;
+use std::path::{Path, PathBuf};
+
+pub fn is_valid_package_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('.')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+/// How a package file refers to its managed payload folder.
+pub fn payload_ref(name: &str, config_paths: &ConfigPaths) -> String {
+    format!("$CONFIG_ROOT_PATH/{}/{}",
+        config_paths.managed_packages_dir.strip_prefix(&config_paths.root)
+            .unwrap_or(&config_paths.managed_packages_dir)
+            .to_string_lossy()
+            .replace('\\', "/"),
+        name)
+}
+
+/// Where `reference` occurs in `content` as a whole folder, so the payload of
+/// `foo` is not mistaken for that of `foo_v2`.
+fn payload_ref_positions(content: &str, reference: &str) -> Vec<usize> {
+    content.match_indices(reference)
+        .filter(|(i, _)| matches!(
+            content[i + reference.len()..].chars().next(),
+            None | Some('/' | '\\' | '"' | ';' | ':')
+        ))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Whether a package file's `content` points at the payload of `name`.
+pub fn references_payload(content: &str, name: &str, config_paths: &ConfigPaths) -> bool {
+    !payload_ref_positions(content, &payload_ref(name, config_paths)).is_empty()
+}
+
+/// Points every reference to the payload of `old` at the payload of `new`.
+pub fn replace_payload_ref(content: &str, old: &str, new: &str, config_paths: &ConfigPaths) -> String {
+    let old_ref = payload_ref(old, config_paths);
+    let new_ref = payload_ref(new, config_paths);
+    let mut out = String::with_capacity(content.len());
+    let mut last = 0;
+    for i in payload_ref_positions(content, &old_ref) {
+        out.push_str(&content[last..i]);
+        out.push_str(&new_ref);
+        last = i + old_ref.len();
+    }
+    out.push_str(&content[last..]);
+    out
+}
+
+/// The payload folder of a package installed by the launcher, recognised by
+/// the package file pointing at it.
+pub fn managed_payload_dir(name: &str, package_file: &Path, config_paths: &ConfigPaths) -> Option<PathBuf> {
+    let dir = config_paths.managed_packages_dir.join(name);
+    let content = std::fs::read_to_string(package_file).ok()?;
+    (dir.is_dir() && references_payload(&content, name, config_paths)).then_some(dir)
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<(), std::io::Error> {
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)?.flatten() {
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            std::fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+fn extract_zip(archive: &Path, to: &Path) -> Result<(), String> {
+    let file = std::fs::File::open(archive)
+        .map_err(|e| format!("Failed to open archive: {}", e))?;
+    let mut zip = zip::ZipArchive::new(file)
+        .map_err(|e| format!("Failed to read archive: {}", e))?;
+    zip.extract(to)
+        .map_err(|e| format!("Failed to extract archive: {}", e))
+}
+
+/// Archives usually wrap everything in one top-level folder; use that folder
+/// as the payload root when it is the only entry.
+fn unwrap_single_dir(dir: &Path) -> PathBuf {
+    let entries: Vec<_> = match std::fs::read_dir(dir) {
+        Ok(entries) => entries.flatten().collect(),
+        Err(_) => return dir.to_path_buf(),
+    };
+    match entries.as_slice() {
+        [only] if only.path().is_dir() => only.path(),
+        _ => dir.to_path_buf(),
+    }
+}
+
+/// The package file shipped in the payload root: `<name>.json`, or the only
+/// JSON file there.
+fn find_package_json(payload: &Path, name: &str) -> Option<PathBuf> {
+    let named = payload.join(format!("{}.json", name));
+    if named.is_file() {
+        return Some(named);
+    }
+    let candidates: Vec<PathBuf> = std::fs::read_dir(payload).ok()?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_file() && p.extension().and_then(|e| e.to_str()) == Some("json"))
+        .filter(|p| !p.to_string_lossy().ends_with(".launcher.json"))
+        .collect();
+    match candidates.as_slice() {
+        [only] => Some(only.clone()),
+        _ => None,
+    }
+}
+
+/// A minimal package that puts the payload on `HOUDINI_PATH`.
+fn generate_package_json(name: &str, payload_ref: &str) -> String {
+    let var = format!(
+        "{}_ROOT",
+        name.to_uppercase().replace(|c: char| !c.is_ascii_alphanumeric(), "_")
+    );
+    let data = serde_json::json!({
+        "env": [{ var.clone(): payload_ref }],
+        "hpath": format!("${}", var),
+    });
+    serde_json::to_string_pretty(&data).unwrap_or_default()
+}
+
+/// Installs a package from a zip archive or a folder. The payload goes to
+/// `managed_packages_dir/<name>` and the package file to `packages_dir`.
+/// An existing package of the same name is only replaced when `overwrite` is
+/// set. Returns the path of the written package file.
+pub fn install_package(
+    source: &Path,
+    name: Option<&str>,
+    overwrite: bool,
+    config_paths: &ConfigPaths,
+) -> Result<PathBuf, String> {
+    if !source.exists() {
+        return Err(format!("Source not found: {}", source.display()));
+    }
+    let is_zip = source.is_file()
+        && source.extension().and_then(|e| e.to_str()).map(|e| e.eq_ignore_ascii_case("zip")) == Some(true);
+    if !is_zip && !source.is_dir() {
+        return Err("Source must be a .zip archive or a folder".to_string());
+    }
+
+    // Folder names such as `mytool-1.2` keep their dots; only an archive's
+    // extension is dropped.
+    let default_name = if is_zip { source.file_stem() } else { source.file_name() };
+    let name = match name {
+        Some(n) => n.trim().to_string(),
+        None => default_name.unwrap_or_default().to_string_lossy().to_string(),
+    };
+    if !is_valid_package_name(&name) {
+        return Err(format!("Invalid package name: {}", name));
+    }
+
+    let package_file = config_paths.packages_dir.join(format!("{}.json", name));
+    let payload_dir = config_paths.managed_packages_dir.join(&name);
+    if !overwrite && (package_file.exists() || payload_dir.exists()) {
+        return Err(format!("Package already exists: {}", name));
+    }
+
+    // Unpack next to the final location first so a failed install leaves the
+    // previous version in place.
+    let staging = config_paths.managed_packages_dir.join(format!(".{}.installing", name));
+    std::fs::remove_dir_all(&staging).ok();
+    std::fs::create_dir_all(&staging)
+        .map_err(|e| format!("Failed to create install dir: {}", e))?;
+
+    let result = (|| {
+        if is_zip {
+            extract_zip(source, &staging)?;
+        } else {
+            copy_dir(source, &staging)
+                .map_err(|e| format!("Failed to copy package folder: {}", e))?;
+        }
+        let payload_root = if is_zip { unwrap_single_dir(&staging) } else { staging.clone() };
+
+        let payload_ref = payload_ref(&name, config_paths);
+        let content = match find_package_json(&payload_root, &name) {
+            // The file now lives in `packages_dir`, so references to its own
+            // folder must point at the payload instead.
+            Some(json) => replace_var(
+                &std::fs::read_to_string(&json)
+                    .map_err(|e| format!("Failed to read package file: {}", e))?,
+                "HOUDINI_PACKAGE_PATH",
+                &payload_ref,
+            ),
+            None => generate_package_json(&name, &payload_ref),
+        };
+
+        let data = parse_lenient(&content)
+            .map_err(|e| format!("Invalid package file: {}", e))?;
+        if !data.is_object() {
+            return Err("Invalid package file: expected a JSON object".to_string());
+        }
+        let spec = PackageSpec::from_value(&data);
+        if spec.env.is_empty() && spec.hpath.is_empty() && spec.package_path.is_empty() && spec.requires.is_empty() {
+            return Err("Invalid package file: it sets no env, hpath, package_path or requires".to_string());
+        }
+        Ok((payload_root, content))
+    })();
+
+    let (payload_root, content) = match result {
+        Ok(r) => r,
+        Err(e) => {
+            std::fs::remove_dir_all(&staging).ok();
+            return Err(e);
+        }
+    };
+
+    // The previous payload is moved aside rather than deleted, and put back
+    // if the new one cannot be put in place.
+    let previous = config_paths.managed_packages_dir.join(format!(".{}.previous", name));
+    std::fs::remove_dir_all(&previous).ok();
+    let had_previous = payload_dir.exists();
+    if had_previous {
+        if let Err(e) = std::fs::rename(&payload_dir, &previous) {
+            std::fs::remove_dir_all(&staging).ok();
+            return Err(format!("Failed to move previous version aside: {}", e));
+        }
+    }
+    let restore_previous = || {
+        if had_previous {
+            std::fs::rename(&previous, &payload_dir).ok();
+        }
+    };
+
+    if let Err(e) = std::fs::rename(&payload_root, &payload_dir) {
+        restore_previous();
+        std::fs::remove_dir_all(&staging).ok();
+        return Err(format!("Failed to move package into place: {}", e));
+    }
+    std::fs::remove_dir_all(&staging).ok();
+
+    let written = std::fs::create_dir_all(&config_paths.packages_dir)
+        .and_then(|_| std::fs::write(&package_file, content));
+    if let Err(e) = written {
+        std::fs::remove_dir_all(&payload_dir).ok();
+        restore_previous();
+        return Err(format!("Failed to write package file: {}", e));
+    }
+    std::fs::remove_dir_all(&previous).ok();
+
+    Ok(package_file)
+}
@@ -0,0 +1,325 @@
+use serde_json::Value;
+
+/// Walks JSON text the way Houdini reads package files: `//` and `/* */`
+/// comments and trailing commas are allowed.
+struct Scanner<'a> {
+    src: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Scanner<'a> {
+    fn new(text: &'a str) -> Self {
+        Self {
+            src: text.as_bytes(),
+            pos: 0,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.src.get(self.pos).copied()
+    }
+
+    fn error(&self, msg: &str) -> String {
+        let line = self.src[..self.pos.min(self.src.len())].iter().filter(|&&b| b == b'\n').count() + 1;
+        format!("{} at line {}", msg, line)
+    }
+
+    fn skip_trivia(&mut self) -> Result<(), String> {
+        loop {
+            match self.peek() {
+                Some(b) if b.is_ascii_whitespace() => self.pos += 1,
+                Some(b'/') if self.src.get(self.pos + 1) == Some(&b'/') => {
+                    while let Some(b) = self.peek() {
+                        if b == b'\n' {
+                            break;
+                        }
+                        self.pos += 1;
+                    }
+                }
+                Some(b'/') if self.src.get(self.pos + 1) == Some(&b'*') => {
+                    let start = self.pos;
+                    self.pos += 2;
+                    loop {
+                        match self.peek() {
+                            None => {
+                                self.pos = start;
+                                return Err(self.error("Unterminated comment"));
+                            }
+                            Some(b'*') if self.src.get(self.pos + 1) == Some(&b'/') => {
+                                self.pos += 2;
+                                break;
+                            }
+                            Some(_) => self.pos += 1,
+                        }
+                    }
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn skip_string(&mut self) -> Result<(), String> {
+        let start = self.pos;
+        self.pos += 1;
+        loop {
+            match self.peek() {
+                None => {
+                    self.pos = start;
+                    return Err(self.error("Unterminated string"));
+                }
+                Some(b'\\') => self.pos += 2,
+                Some(b'"') => {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                Some(_) => self.pos += 1,
+            }
+        }
+    }
+
+    fn skip_value(&mut self) -> Result<(), String> {
+        match self.peek() {
+            Some(b'"') => self.skip_string(),
+            Some(open @ (b'{' | b'[')) => {
+                let close = if open == b'{' { b'}' } else { b']' };
+                self.pos += 1;
+                loop {
+                    self.skip_trivia()?;
+                    match self.peek() {
+                        None => return Err(self.error("Unexpected end of file")),
+                        Some(b) if b == close => {
+                            self.pos += 1;
+                            return Ok(());
+                        }
+                        Some(b',' | b':') => self.pos += 1,
+                        Some(_) => self.skip_value()?,
+                    }
+                }
+            }
+            Some(_) => {
+                let start = self.pos;
+                while let Some(b) = self.peek() {
+                    if b.is_ascii_whitespace() || matches!(b, b',' | b'}' | b']' | b':' | b'/') {
+                        break;
+                    }
+                    self.pos += 1;
+                }
+                if self.pos == start {
+                    return Err(self.error("Unexpected character"));
+                }
+                Ok(())
+            }
+            None => Err(self.error("Unexpected end of file")),
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), String> {
+        if self.peek() == Some(byte) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("Expected '{}'", byte as char)))
+        }
+    }
+}
+
+/// Rewrites lenient JSON as strict JSON by dropping comments and trailing commas.
+fn to_strict(text: &str) -> Result<String, String> {
+    let mut scanner = Scanner::new(text);
+    let mut out = String::with_capacity(text.len());
+
+    while let Some(b) = scanner.peek() {
+        match b {
+            b'"' => {
+                let start = scanner.pos;
+                scanner.skip_string()?;
+                out.push_str(&text[start..scanner.pos]);
+            }
+            b'/' if matches!(scanner.src.get(scanner.pos + 1), Some(b'/' | b'*')) => {
+                scanner.skip_trivia()?;
+                out.push(' ');
+            }
+            b',' => {
+                scanner.pos += 1;
+                let after = scanner.pos;
+                scanner.skip_trivia()?;
+                if !matches!(scanner.peek(), Some(b'}' | b']')) {
+                    out.push(',');
+                }
+                out.push_str(&" ".repeat(scanner.pos - after));
+            }
+            _ => {
+                let start = scanner.pos;
+                scanner.pos += 1;
+                while scanner.peek().is_some_and(|b| !matches!(b, b'"' | b'/' | b',')) {
+                    scanner.pos += 1;
+                }
+                out.push_str(&text[start..scanner.pos]);
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+/// Byte order mark some Windows editors put at the start of the file.
+const BOM: char = '\u{feff}';
+
+/// Parses a package file, accepting comments, trailing commas and a leading
+/// byte order mark.
+pub fn parse_lenient(text: &str) -> Result<Value, String> {
+    let strict = to_strict(text.strip_prefix(BOM).unwrap_or(text))?;
+    serde_json::from_str(&strict).map_err(|e| e.to_string())
+}
+
+/// Sets a top-level key, replacing only the text of its value so comments,
+/// key order and indentation elsewhere in the file are kept. A missing key is
+/// inserted as the first member, indented like the existing ones and with the
+/// file's own line endings.
+pub fn set_top_level_key(text: &str, key: &str, value: &Value) -> Result<String, String> {
+    if let Some(rest) = text.strip_prefix(BOM) {
+        return set_top_level_key(rest, key, value).map(|out| format!("{}{}", BOM, out));
+    }
+    let rendered = serde_json::to_string(value).map_err(|e| e.to_string())?;
+
+    let mut scanner = Scanner::new(text);
+    scanner.skip_trivia()?;
+    scanner.expect(b'{')?;
+    let body_start = scanner.pos;
+    let mut first_member: Option<usize> = None;
+
+    loop {
+        scanner.skip_trivia()?;
+        match scanner.peek() {
+            Some(b'}') => break,
+            Some(b'"') => {}
+            _ => return Err(scanner.error("Expected a key")),
+        }
+
+        let key_start = scanner.pos;
+        first_member.get_or_insert(key_start);
+        scanner.skip_string()?;
+        let member_key: String = serde_json::from_str(&text[key_start..scanner.pos])
+            .map_err(|e| e.to_string())?;
+
+        scanner.skip_trivia()?;
+        scanner.expect(b':')?;
+        scanner.skip_trivia()?;
+        let value_start = scanner.pos;
+        scanner.skip_value()?;
+
+        if member_key == key {
+            return Ok(format!("{}{}{}", &text[..value_start], rendered, &text[scanner.pos..]));
+        }
+
+        scanner.skip_trivia()?;
+        match scanner.peek() {
+            Some(b',') => scanner.pos += 1,
+            Some(b'}') => break,
+            _ => return Err(scanner.error("Expected ',' or '}'")),
+        }
+    }
+
+    let member = format!("{}: {}", serde_json::to_string(key).map_err(|e| e.to_string())?, rendered);
+    let newline = if text.contains("\r\n") { "\r\n" } else { "\n" };
+    match first_member {
+        Some(first) => {
+            let before = &text[body_start..first];
+            match before.rsplit_once('\n') {
+                Some((_, indent)) if indent.trim().is_empty() => Ok(format!(
+                    "{}{}{}{},{}", &text[..body_start], newline, indent, member, &text[body_start..]
+                )),
+                _ => Ok(format!("{}{}, {}", &text[..first], member, &text[first..])),
+            }
+        }
+        None => Ok(format!(
+            "{}{}    {}{}{}", &text[..body_start], newline, member, newline, &text[body_start..].trim_start()
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn comments_with_quotes_and_commas_are_ignored() {
+        let text = r#"{
+            // "quoted", with commas, }
+            "a": 1, /* "b": 2, */
+            "c": "x // not a comment"
+        }"#;
+        assert_eq!(parse_lenient(text).unwrap(), json!({"a": 1, "c": "x // not a comment"}));
+    }
+
+    #[test]
+    fn trailing_commas_before_closing_brackets() {
+        assert_eq!(parse_lenient(r#"{"a": [1, 2,], "b": {"c": 3,},}"#).unwrap(), json!({"a": [1, 2], "b": {"c": 3}}));
+        assert_eq!(parse_lenient("{\"a\": [1, // last\n],\n}").unwrap(), json!({"a": [1]}));
+    }
+
+    #[test]
+    fn escaped_quotes_in_strings() {
+        let text = r#"{"a": "say \"hi\", // still a string", "b": "C:\\path\\"}"#;
+        assert_eq!(parse_lenient(text).unwrap(), json!({"a": "say \"hi\", // still a string", "b": "C:\\path\\"}));
+    }
+
+    #[test]
+    fn unterminated_input_is_an_error() {
+        assert!(parse_lenient(r#"{"a": "open}"#).is_err());
+        assert!(parse_lenient(r#"{"a": 1 /* open"#).is_err());
+    }
+
+    #[test]
+    fn insert_into_empty_object() {
+        let out = set_top_level_key("{}", "enable", &json!(true)).unwrap();
+        assert_eq!(parse_lenient(&out).unwrap(), json!({"enable": true}));
+        let out = set_top_level_key("{ }", "enable", &json!(true)).unwrap();
+        assert_eq!(parse_lenient(&out).unwrap(), json!({"enable": true}));
+    }
+
+    #[test]
+    fn insert_into_single_line_object() {
+        let out = set_top_level_key(r#"{"a": 1}"#, "b", &json!(2)).unwrap();
+        assert_eq!(out, r#"{"b": 2, "a": 1}"#);
+    }
+
+    #[test]
+    fn insert_uses_existing_indentation() {
+        let text = "{\n    // header\n    \"a\": 1\n}\n";
+        let out = set_top_level_key(text, "b", &json!("x")).unwrap();
+        assert_eq!(out, "{\n    \"b\": \"x\",\n    // header\n    \"a\": 1\n}\n");
+    }
+
+    #[test]
+    fn leading_byte_order_mark() {
+        assert_eq!(parse_lenient("\u{feff}{\"a\":1}").unwrap(), json!({"a": 1}));
+        let out = set_top_level_key("\u{feff}{\"a\": 1}", "a", &json!(2)).unwrap();
+        assert_eq!(out, "\u{feff}{\"a\": 2}");
+    }
+
+    #[test]
+    fn insert_keeps_crlf_line_endings() {
+        let out = set_top_level_key("{\r\n    \"a\": 1\r\n}\r\n", "b", &json!(2)).unwrap();
+        assert_eq!(out, "{\r\n    \"b\": 2,\r\n    \"a\": 1\r\n}\r\n");
+        let out = set_top_level_key("{\r\n}\r\n", "b", &json!(2)).unwrap();
+        assert_eq!(out, "{\r\n    \"b\": 2\r\n}\r\n");
+    }
+
+    #[test]
+    fn replace_value_followed_by_comment() {
+        let text = "{\n    \"enable\": false, // off for now\n    \"env\": [] /* keep */\n}\n";
+        let out = set_top_level_key(text, "enable", &json!(true)).unwrap();
+        assert_eq!(out, "{\n    \"enable\": true, // off for now\n    \"env\": [] /* keep */\n}\n");
+        let out = set_top_level_key(text, "env", &json!([{"A": "1"}])).unwrap();
+        assert_eq!(out, "{\n    \"enable\": false, // off for now\n    \"env\": [{\"A\":\"1\"}] /* keep */\n}\n");
+    }
+
+    #[test]
+    fn replace_only_matches_top_level_keys() {
+        let text = r#"{"env": [{"enable": 1}], "enable": false}"#;
+        let out = set_top_level_key(text, "enable", &json!(true)).unwrap();
+        assert_eq!(out, r#"{"env": [{"enable": 1}], "enable": true}"#);
+    }
+}
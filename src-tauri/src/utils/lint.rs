@@ -0,0 +1,241 @@
+use crate::models::{EnvMethod, LintFinding, LintSeverity, Package, PackageEnable};
+use crate::utils::expression::{evaluate, ExprContext};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::path::Path;
+
+/// Top-level keys Houdini reads from a package file, plus the launcher's own
+/// metadata block.
+const KNOWN_KEYS: &[&str] = &[
+    "enable",
+    "env",
+    "hpath",
+    "path",
+    "package_path",
+    "load_package_once",
+    "recursive_path",
+    "requires",
+    "show",
+    "version",
+    "launcher",
+];
+
+/// System search paths that lose every other entry when replaced.
+const SYSTEM_PATH_VARS: &[&str] = &["PATH", "PYTHONPATH", "LD_LIBRARY_PATH", "DYLD_LIBRARY_PATH"];
+
+/// Escapes one JSON pointer reference token.
+fn pointer_token(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// One variable set by an `env` entry.
+struct EnvSite<'a> {
+    key: &'a str,
+    pointer: String,
+    value_pointer: String,
+    value: &'a Value,
+    method: EnvMethod,
+}
+
+fn env_sites(data: &Value) -> Vec<EnvSite<'_>> {
+    let mut sites = Vec::new();
+    let Some(entries) = data.get("env").and_then(|v| v.as_array()) else {
+        return sites;
+    };
+
+    for (i, entry) in entries.iter().enumerate() {
+        let Some(obj) = entry.as_object() else {
+            continue;
+        };
+        let method_of = |v: &Value| {
+            v.get("method").and_then(|m| m.as_str()).map(EnvMethod::parse).unwrap_or_default()
+        };
+
+        if let (Some(key), Some(value)) = (obj.get("var").and_then(|v| v.as_str()), obj.get("value")) {
+            sites.push(EnvSite {
+                key,
+                pointer: format!("/env/{}", i),
+                value_pointer: format!("/env/{}/value", i),
+                value,
+                method: method_of(entry),
+            });
+            continue;
+        }
+
+        for (key, value) in obj {
+            let pointer = format!("/env/{}/{}", i, pointer_token(key));
+            let site = match value.as_object().and_then(|inner| inner.get("value")) {
+                Some(inner) => EnvSite {
+                    key,
+                    value_pointer: format!("{}/value", pointer),
+                    pointer,
+                    value: inner,
+                    method: method_of(value),
+                },
+                None => EnvSite {
+                    key,
+                    value_pointer: pointer.clone(),
+                    pointer,
+                    value,
+                    method: EnvMethod::default(),
+                },
+            };
+            sites.push(site);
+        }
+    }
+    sites
+}
+
+/// Collects every string in a value with its pointer and the condition
+/// guarding it, if any.
+fn strings<'a>(value: &'a Value, pointer: String, condition: Option<&'a str>, out: &mut Vec<(String, &'a str, Option<&'a str>)>) {
+    match value {
+        Value::String(s) => out.push((pointer, s, condition)),
+        Value::Array(arr) => {
+            for (i, v) in arr.iter().enumerate() {
+                strings(v, format!("{}/{}", pointer, i), condition, out);
+            }
+        }
+        Value::Object(obj) => {
+            for (cond, v) in obj {
+                strings(v, format!("{}/{}", pointer, pointer_token(cond)), Some(cond), out);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Whether an `enable` expression only ever holds on Windows: false on every
+/// other OS for a spread of Houdini and Python versions, and true on Windows
+/// for at least one of them.
+fn limited_to_windows(expr: &str) -> bool {
+    const VERSIONS: &[&str] = &["18.5.499", "19.0.383", "19.5.303", "20.0.506", "20.5.278", "21.0.440"];
+    const PYTHONS: &[&str] = &["python3.7", "python3.9", "python3.10", "python3.11"];
+    let holds = |os: &str| -> Vec<bool> {
+        VERSIONS.iter()
+            .flat_map(|v| PYTHONS.iter().map(move |p| (v, p)))
+            .map(|(v, p)| {
+                let ctx = ExprContext {
+                    houdini_version: v.to_string(),
+                    houdini_os: os.to_string(),
+                    houdini_python: p.to_string(),
+                };
+                evaluate(expr, &ctx).unwrap_or(false)
+            })
+            .collect()
+    };
+    holds("windows").contains(&true) && !holds("linux").contains(&true) && !holds("macos").contains(&true)
+}
+
+fn references(value: &str, var: &str) -> bool {
+    value.contains(&format!("${}", var)) || value.contains(&format!("${{{}}}", var))
+}
+
+/// Checks one package file for common mistakes. `config_root` is used to spot
+/// absolute paths that should be written relative to `$CONFIG_ROOT_PATH`.
+pub fn lint_package(pkg: &Package, config_root: &Path) -> Vec<LintFinding> {
+    let mut findings = Vec::new();
+    let mut add = |rule: &str, severity: LintSeverity, pointer: String, message: String| {
+        findings.push(LintFinding {
+            package: pkg.name.clone(),
+            rule: rule.to_string(),
+            severity,
+            pointer,
+            message,
+        });
+    };
+
+    let Some(obj) = pkg.data.as_object() else {
+        add("not_an_object", LintSeverity::Error, String::new(), "Package file is not a JSON object".to_string());
+        return findings;
+    };
+
+    for key in obj.keys().filter(|k| !KNOWN_KEYS.contains(&k.as_str())) {
+        add(
+            "unknown_key",
+            LintSeverity::Warning,
+            format!("/{}", pointer_token(key)),
+            format!("Houdini ignores the key \"{}\"", key),
+        );
+    }
+
+    let mut values = Vec::new();
+    let mut seen = HashSet::new();
+    for site in env_sites(&pkg.data) {
+        let mut site_values = Vec::new();
+        strings(site.value, site.value_pointer.clone(), None, &mut site_values);
+        let self_referencing = site_values.iter().any(|(_, v, _)| references(v, site.key));
+
+        if !seen.insert(site.key.to_string()) {
+            add(
+                "duplicate_env_key",
+                LintSeverity::Warning,
+                site.pointer.clone(),
+                format!("{} is set more than once in this package", site.key),
+            );
+        }
+
+        let is_system_path = SYSTEM_PATH_VARS.iter().any(|v| v.eq_ignore_ascii_case(site.key));
+        if is_system_path && site.method == EnvMethod::Replace && !self_referencing {
+            add(
+                "path_replaced",
+                LintSeverity::Error,
+                site.pointer.clone(),
+                format!("{} is replaced; use \"method\": \"prepend\" or include ${}", site.key, site.key),
+            );
+        }
+
+        let is_houdini_path = site.key.starts_with("HOUDINI_")
+            && site.key.ends_with("_PATH")
+            && site.key != "HOUDINI_PACKAGE_PATH";
+        if is_houdini_path
+            && site.method == EnvMethod::Replace
+            && !self_referencing
+            && !site_values.iter().any(|(_, v, _)| v.contains('&'))
+        {
+            add(
+                "missing_ampersand",
+                LintSeverity::Warning,
+                site.pointer.clone(),
+                format!("{} drops Houdini's default search path; add \"&\" or prepend instead", site.key),
+            );
+        }
+
+        values.extend(site_values);
+    }
+
+    for key in ["hpath", "path", "package_path"] {
+        if let Some(v) = obj.get(key) {
+            strings(v, format!("/{}", key), None, &mut values);
+        }
+    }
+
+    let root = config_root.to_string_lossy().replace('\\', "/");
+    let root = root.trim_end_matches('/');
+    let windows_package = matches!(&pkg.spec.enable, PackageEnable::Expr(expr) if limited_to_windows(expr));
+    for (pointer, value, condition) in values {
+        let windows_only = windows_package || condition.is_some_and(|c| c.contains("windows"));
+        if value.contains('\\') && !windows_only {
+            add(
+                "windows_separator",
+                LintSeverity::Warning,
+                pointer.clone(),
+                "Backslashes only work on Windows; use \"/\" in shared packages".to_string(),
+            );
+        }
+
+        let normalized = value.replace('\\', "/");
+        let under_root = !root.is_empty()
+            && normalized.strip_prefix(root).is_some_and(|rest| rest.is_empty() || rest.starts_with('/'));
+        if under_root {
+            add(
+                "absolute_config_path",
+                LintSeverity::Info,
+                pointer,
+                format!("Path is inside the config root; write it as $CONFIG_ROOT_PATH{}", &normalized[root.len()..]),
+            );
+        }
+    }
+
+    findings
+}
@@ -1 +1,11 @@
 pub mod config;
+pub mod conflicts;
+pub mod dependencies;
+pub mod expand;
+pub mod expression;
+pub mod graph;
+pub mod install;
+pub mod json_edit;
+pub mod lint;
+pub mod script_export;
+pub mod staging;
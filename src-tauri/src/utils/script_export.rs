@@ -0,0 +1,98 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScriptFormat {
+    Sh,
+    Bat,
+    Dotenv,
+}
+
+impl ScriptFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ScriptFormat::Sh => "sh",
+            ScriptFormat::Bat => "bat",
+            ScriptFormat::Dotenv => "env",
+        }
+    }
+}
+
+fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn sh_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r#"'\''"#))
+}
+
+/// Inside `set "NAME=value"` only `%` needs escaping; a double quote cannot
+/// be represented at all.
+fn bat_quote(value: &str) -> Result<String, String> {
+    if value.contains('"') || value.contains('\n') {
+        return Err(format!("Value cannot be written to a batch file: {}", value));
+    }
+    Ok(value.replace('%', "%%"))
+}
+
+fn dotenv_quote(value: &str) -> String {
+    let escaped = value
+        .replace('\\', r"\\")
+        .replace('"', r#"\""#)
+        .replace('$', r"\$")
+        .replace('\n', r"\n");
+    format!("\"{}\"", escaped)
+}
+
+/// Renders the variables the launcher sets, the working directory and the
+/// executable as a standalone script. `env` is applied in order, so later
+/// entries override earlier ones just like at launch.
+pub fn render_launch_script(
+    format: ScriptFormat,
+    exe: &Path,
+    working_dir: &Path,
+    env: &[(String, String)],
+) -> Result<String, String> {
+    if let Some((name, _)) = env.iter().find(|(name, _)| !is_valid_name(name)) {
+        return Err(format!("Invalid environment variable name: {}", name));
+    }
+
+    let exe = exe.to_string_lossy();
+    let working_dir = working_dir.to_string_lossy();
+    let mut out = String::new();
+
+    match format {
+        ScriptFormat::Sh => {
+            out.push_str("#!/bin/sh\n");
+            out.push_str("# Generated by Houdini Launcher\n\n");
+            for (name, value) in env {
+                out.push_str(&format!("export {}={}\n", name, sh_quote(value)));
+            }
+            out.push_str(&format!("\ncd {} || exit 1\n", sh_quote(&working_dir)));
+            out.push_str(&format!("exec {} \"$@\"\n", sh_quote(&exe)));
+        }
+        ScriptFormat::Bat => {
+            out.push_str("@echo off\r\n");
+            out.push_str("rem Generated by Houdini Launcher\r\n");
+            out.push_str("setlocal\r\n\r\n");
+            for (name, value) in env {
+                out.push_str(&format!("set \"{}={}\"\r\n", name, bat_quote(value)?));
+            }
+            out.push_str(&format!("\r\ncd /d \"{}\"\r\n", bat_quote(&working_dir)?));
+            out.push_str(&format!("\"{}\" %*\r\n", bat_quote(&exe)?));
+        }
+        ScriptFormat::Dotenv => {
+            out.push_str("# Generated by Houdini Launcher\n");
+            out.push_str(&format!("# Executable: {}\n", exe));
+            out.push_str(&format!("# Working directory: {}\n", working_dir));
+            for (name, value) in env {
+                out.push_str(&format!("{}={}\n", name, dotenv_quote(value)));
+            }
+        }
+    }
+
+    Ok(out)
+}
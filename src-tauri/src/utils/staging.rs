@@ -0,0 +1,100 @@
+use crate::models::{Package, PackageEnable};
+use crate::utils::config::get_staging_root;
+use crate::utils::conflicts::prioritized_load_order;
+use crate::utils::expand::replace_var;
+use crate::utils::json_edit::set_top_level_key;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// JSON-escaped form of a path, for splicing into the text of a package file.
+fn json_escaped(path: &Path) -> String {
+    let quoted = serde_json::to_string(&path.to_string_lossy()).unwrap_or_default();
+    quoted.trim_matches('"').to_string()
+}
+
+/// Copies the chosen package files into a private directory that can be passed
+/// to Houdini as `HOUDINI_PACKAGE_DIR`, leaving the shared files untouched.
+///
+/// Houdini sets `$HOUDINI_PACKAGE_PATH` to the folder of the package file it
+/// is reading, so references to it are pointed back at the original folder.
+/// Files that disable themselves with a plain `"enable": false` are switched
+/// on, since the package was chosen explicitly.
+///
+/// With a `priority` list (see `prioritized_load_order`) every file name gets
+/// a numeric prefix so Houdini reads them in that order. `requires` entries
+/// are rewritten to the prefixed names so Houdini still finds them.
+pub fn stage_packages(packages: &[&Package], priority: &[String]) -> Result<PathBuf, String> {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    let dir = get_staging_root().join(format!("{}-{}", std::process::id(), nanos));
+    stage_packages_into(&dir, packages, priority)?;
+    Ok(dir)
+}
+
+/// Like `stage_packages`, but into a directory that outlives the launch.
+/// Package files left in `dir` by an earlier staging are removed first.
+pub fn stage_packages_into(dir: &Path, packages: &[&Package], priority: &[String]) -> Result<(), String> {
+    std::fs::create_dir_all(dir)
+        .map_err(|e| format!("Failed to create staging dir: {}", e))?;
+    let entries = std::fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read staging dir: {}", e))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_file() && path.extension().and_then(|e| e.to_str()) == Some("json") {
+            std::fs::remove_file(&path)
+                .map_err(|e| format!("Failed to clear staging dir: {}", e))?;
+        }
+    }
+
+    let ordered = if priority.is_empty() {
+        packages.to_vec()
+    } else {
+        prioritized_load_order(packages, priority)
+    };
+    let staged_name = |pkg: &Package| -> String {
+        let stem = pkg.file_path.file_stem()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| pkg.name.clone());
+        match ordered.iter().position(|p| p.name == pkg.name) {
+            Some(i) if !priority.is_empty() => format!("{:03}_{}", i, stem),
+            _ => stem,
+        }
+    };
+
+    for pkg in &ordered {
+        let content = std::fs::read_to_string(&pkg.file_path)
+            .map_err(|e| format!("Failed to read package {}: {}", pkg.file_path.display(), e))?;
+
+        let source_dir = json_escaped(pkg.file_path.parent().unwrap_or(Path::new(".")));
+        let mut content = replace_var(&content, "HOUDINI_PACKAGE_PATH", &source_dir);
+
+        if pkg.spec.enable == PackageEnable::Bool(false) {
+            content = set_top_level_key(&content, "enable", &serde_json::Value::Bool(true))
+                .map_err(|e| format!("Failed to enable package {}: {}", pkg.name, e))?;
+        }
+
+        if !priority.is_empty() && !pkg.spec.requires.is_empty() {
+            let requires: Vec<String> = pkg.spec.requires.iter()
+                .map(|r| match ordered.iter().find(|p| p.name.eq_ignore_ascii_case(r.trim())) {
+                    Some(dep) => staged_name(dep),
+                    None => r.clone(),
+                })
+                .collect();
+            content = set_top_level_key(&content, "requires", &serde_json::json!(requires))
+                .map_err(|e| format!("Failed to update requires of {}: {}", pkg.name, e))?;
+        }
+
+        std::fs::write(dir.join(format!("{}.json", staged_name(pkg))), content)
+            .map_err(|e| format!("Failed to stage package {}: {}", pkg.name, e))?;
+    }
+
+    Ok(())
+}
+
+pub fn remove_staged(dir: &Path) {
+    if dir.starts_with(get_staging_root()) {
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
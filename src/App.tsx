@@ -155,8 +155,13 @@ function App() {
   const handleLaunch = async () => {
     if (!selectedVersion) return;
     try {
-      const exePath = await api.getHoudiniExePath(selectedVersion.path);
-      await api.launchHoudini(exePath, configPaths.packages_dir, configPaths.root, []);
+      const preset = presets.find(p => p.name === currentPreset);
+      const exePath = await api.getHoudiniExePath(selectedVersion.path, preset?.executable || undefined);
+      await api.launchHoudini({
+        exe_path: exePath,
+        config_root: configPaths.root,
+        preset: currentPreset ?? undefined,
+      });
     } catch (error) {
       console.error('Failed to launch Houdini:', error);
     }
@@ -1,17 +1,193 @@
 import { invoke } from '@tauri-apps/api/core';
+import { listen, UnlistenFn } from '@tauri-apps/api/event';
+
+export interface HoudiniExecutable {
+  name: string;
+  path: string;
+}
+
+export type HoudiniBuildType = 'production' | 'daily' | 'unknown';
 
 export interface HoudiniVersion {
   name: string;
   path: string;
   bin_path: string;
+  root: string;
+  major: number;
+  minor: number;
+  build: number;
+  executables: HoudiniExecutable[];
+  python_version: string | null;
+  build_type: HoudiniBuildType;
+  is_alias: boolean;
+}
+
+export type HoudiniRootStatus = 'reachable' | 'empty' | 'missing';
+
+export interface HoudiniRoot {
+  path: string;
+  status: HoudiniRootStatus;
+  version_count: number;
+  user_defined: boolean;
+}
+
+export type EnvMethod = 'prepend' | 'append' | 'replace';
+
+export interface EnvValue {
+  value: string;
+  condition: string | null;
+}
+
+export interface EnvEntry {
+  key: string;
+  values: EnvValue[];
+  method: EnvMethod;
+}
+
+export type PackageEnable =
+  | { kind: 'bool'; value: boolean }
+  | { kind: 'expr'; value: string };
+
+export interface PackageSpec {
+  enable: PackageEnable;
+  env: EnvEntry[];
+  hpath: EnvValue[];
+  package_path: EnvValue[];
+  load_package_once: boolean;
+  recursive_path: boolean;
+  requires: string[];
+}
+
+export interface PackageMetadata {
+  description: string | null;
+  version: string | null;
+  author: string | null;
+  tags: string[];
+  icon: string | null;
+  homepage: string | null;
 }
 
 export interface Package {
   name: string;
   file_path: string;
   data: Record<string, unknown>;
+  spec: PackageSpec;
+  metadata: PackageMetadata;
   enabled: boolean;
   config_root: string;
+  source: string;
+  shadows: string[];
+}
+
+export interface PackageSource {
+  name: string;
+  path: string;
+  max_depth: number;
+  priority: number;
+}
+
+export interface PackageLoadStatus {
+  name: string;
+  loads: boolean;
+  reason: string;
+}
+
+export type PathProblem = 'missing' | 'not_a_directory' | 'unreadable' | 'unresolved';
+
+export interface PathIssue {
+  key: string;
+  raw: string;
+  expanded: string;
+  problem: PathProblem;
+  detail: string | null;
+}
+
+export interface PackageDiagnostics {
+  name: string;
+  issues: PathIssue[];
+}
+
+export type LintSeverity = 'error' | 'warning' | 'info';
+
+export interface LintFinding {
+  package: string;
+  rule: string;
+  severity: LintSeverity;
+  pointer: string;
+  message: string;
+}
+
+export type DependencyIssueKind = 'missing' | 'disabled' | 'cycle' | 'duplicate';
+
+export interface DependencyIssue {
+  package: string;
+  dependency: string;
+  kind: DependencyIssueKind;
+  detail: string;
+}
+
+export interface LoadPlan {
+  packages: string[];
+  pulled_in: string[];
+  issues: DependencyIssue[];
+}
+
+export interface EnvContribution {
+  package: string;
+  value: string;
+  method: EnvMethod;
+}
+
+export interface EnvConflict {
+  variable: string;
+  contributions: EnvContribution[];
+  winner: string;
+  overwritten: string[];
+  final_value: string | null;
+}
+
+export interface EnvSource {
+  source: string;
+  value: string;
+  method: EnvMethod;
+}
+
+export interface EnvVariable {
+  name: string;
+  value: string;
+  sources: EnvSource[];
+  error: string | null;
+}
+
+export type ScriptFormat = 'sh' | 'bat' | 'dotenv';
+
+export type GraphFormat = 'dot' | 'json';
+
+export type GraphNodeStatus = 'enabled' | 'disabled' | 'missing';
+
+export interface GraphNode {
+  id: string;
+  status: GraphNodeStatus;
+}
+
+export type GraphEdgeKind = 'requires' | 'shared_variable' | 'variable_reference';
+
+export interface GraphEdge {
+  from: string;
+  to: string;
+  kind: GraphEdgeKind;
+  variables: string[];
+}
+
+export interface PackageGraph {
+  nodes: GraphNode[];
+  edges: GraphEdge[];
+}
+
+export interface ChangeSet {
+  added: string[];
+  removed: string[];
+  changed: string[];
 }
 
 export interface PresetData {
@@ -20,11 +196,22 @@ export interface PresetData {
   houdini: string;
   avatar: string;
   avatar_path: string;
+  executable: string;
+  priority: string[];
+}
+
+export interface LaunchRequest {
+  exe_path?: string;
+  config_root: string;
+  env_vars?: [string, string][];
+  packages?: string[];
+  preset?: string;
 }
 
 export interface ConfigPaths {
   root: string;
   packages_dir: string;
+  managed_packages_dir: string;
   global_vars_file: string;
   presets_file: string;
   favorites_file: string;
@@ -51,6 +238,10 @@ class ApiService {
     return invoke('list_houdini_versions');
   }
 
+  async resolveHoudiniVersion(spec: string): Promise<HoudiniVersion> {
+    return invoke('resolve_houdini_version', { spec });
+  }
+
   async getHoudiniExePath(versionPath: string, exeName?: string): Promise<string> {
     return invoke('get_houdini_exe_path', { versionPath, exeName });
   }
@@ -59,6 +250,26 @@ class ApiService {
     return invoke('save_houdini_exe', { exePath });
   }
 
+  async loadSavedHoudiniRoot(): Promise<string | null> {
+    return invoke('load_saved_houdini_root');
+  }
+
+  async saveHoudiniRoot(root: string): Promise<void> {
+    return invoke('save_houdini_root', { root });
+  }
+
+  async listHoudiniRoots(): Promise<HoudiniRoot[]> {
+    return invoke('list_houdini_roots');
+  }
+
+  async addHoudiniRoot(root: string): Promise<HoudiniRoot[]> {
+    return invoke('add_houdini_root', { root });
+  }
+
+  async removeHoudiniRoot(root: string): Promise<HoudiniRoot[]> {
+    return invoke('remove_houdini_root', { root });
+  }
+
   async loadSavedHoudiniExe(): Promise<string | null> {
     return invoke('load_saved_houdini_exe');
   }
@@ -72,6 +283,58 @@ class ApiService {
     return invoke('save_package_enabled', { packageName: name, enabled });
   }
 
+  async installPackage(source: string, name: string | null, overwrite: boolean): Promise<Package> {
+    return invoke('install_package', { source, name, overwrite });
+  }
+
+  async deletePackage(name: string): Promise<void> {
+    return invoke('delete_package', { packageName: name });
+  }
+
+  async renamePackage(name: string, newName: string): Promise<Package> {
+    return invoke('rename_package', { packageName: name, newName });
+  }
+
+  async duplicatePackage(name: string, newName: string): Promise<Package> {
+    return invoke('duplicate_package', { packageName: name, newName });
+  }
+
+  async getPackageTags(): Promise<Record<string, string[]>> {
+    return invoke('get_package_tags');
+  }
+
+  async listPackageSources(): Promise<PackageSource[]> {
+    return invoke('list_package_sources');
+  }
+
+  async savePackageSources(sources: PackageSource[]): Promise<void> {
+    return invoke('save_package_sources', { sources });
+  }
+
+  async checkPackageEnable(houdini: string): Promise<PackageLoadStatus[]> {
+    return invoke('check_package_enable', { houdini });
+  }
+
+  async getPackageDiagnostics(houdini?: string, preset?: string): Promise<PackageDiagnostics[]> {
+    return invoke('get_package_diagnostics', { houdini, preset });
+  }
+
+  async lintPackages(): Promise<LintFinding[]> {
+    return invoke('lint_packages');
+  }
+
+  async resolvePresetPackages(packages: string[], houdini?: string): Promise<LoadPlan> {
+    return invoke('resolve_preset_packages', { packages, houdini });
+  }
+
+  async getEnvConflicts(houdini?: string, preset?: string): Promise<EnvConflict[]> {
+    return invoke('get_env_conflicts', { houdini, preset });
+  }
+
+  async exportPackageGraph(format: GraphFormat, houdini?: string): Promise<string> {
+    return invoke('export_package_graph', { format, houdini });
+  }
+
   async loadFavorites(): Promise<string[]> {
     return invoke('load_favorites');
   }
@@ -89,8 +352,8 @@ class ApiService {
     return invoke('save_presets', { presets, defaultPreset });
   }
 
-  async createPreset(name: string, packages: string[], houdini: string): Promise<PresetData> {
-    return invoke('create_preset', { name, packages, houdini });
+  async createPreset(name: string, packages: string[], houdini: string, executable?: string): Promise<PresetData> {
+    return invoke('create_preset', { name, packages, houdini, executable });
   }
 
   async deletePreset(name: string): Promise<void> {
@@ -102,13 +365,16 @@ class ApiService {
   }
 
   // Launch
-  async launchHoudini(
-    exePath: string,
-    packageDir: string,
-    configRoot: string,
-    envVars: [string, string][]
-  ): Promise<void> {
-    return invoke('launch_houdini', { exePath, packageDir, configRoot, envVars });
+  async launchHoudini(request: LaunchRequest): Promise<void> {
+    return invoke('launch_houdini', { request });
+  }
+
+  async previewLaunchEnvironment(request: LaunchRequest, houdini?: string): Promise<EnvVariable[]> {
+    return invoke('preview_launch_environment', { houdini, request });
+  }
+
+  async exportLaunchScript(format: ScriptFormat, request: LaunchRequest, outputPath?: string): Promise<string> {
+    return invoke('export_launch_script', { format, request, outputPath });
   }
 
   // Settings
@@ -140,6 +406,19 @@ class ApiService {
   async centerWindow(): Promise<void> {
     return invoke('center_window');
   }
+
+  // Live reload
+  onPackagesChanged(handler: (changes: ChangeSet) => void): Promise<UnlistenFn> {
+    return listen<ChangeSet>('packages-changed', (event) => handler(event.payload));
+  }
+
+  onPresetsChanged(handler: (changes: ChangeSet) => void): Promise<UnlistenFn> {
+    return listen<ChangeSet>('presets-changed', (event) => handler(event.payload));
+  }
+
+  onFavoritesChanged(handler: (changes: ChangeSet) => void): Promise<UnlistenFn> {
+    return listen<ChangeSet>('favorites-changed', (event) => handler(event.payload));
+  }
 }
 
 export const api = new ApiService();